@@ -0,0 +1,6 @@
+#!/bin/sh
+
+#the tables import writes with --output sqlite, D1 takes the dump without its transaction
+(cd ../import; cargo run -- --output sqlite:vax.db --output bulk.json)
+sqlite3 ../import/vax.db .dump | grep -v -e '^BEGIN TRANSACTION;$' -e '^COMMIT;$' > ../import/vax.sql
+wrangler d1 execute vax --file ../import/vax.sql
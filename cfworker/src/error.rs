@@ -0,0 +1,86 @@
+use serde::Serialize;
+use thiserror::Error;
+use vaxrender::ErrorPage;
+
+//what can go wrong while answering a request
+#[derive(Error, Debug)]
+pub enum AppError {
+    //nearest is the closest day the index has a report for
+    #[error("no report for `{key}`")]
+    NotFound {
+        key: String,
+        nearest: Option<String>,
+    },
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("storage failure: {0}")]
+    Storage(String),
+    #[error("render failure: {0}")]
+    Render(String),
+}
+
+pub type AppResult<T> = std::result::Result<T, AppError>;
+
+//body sent to clients asking for json
+#[derive(Serialize)]
+pub struct ErrorBody<'a> {
+    pub status: u16,
+    pub error: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nearest: Option<&'a str>,
+}
+
+//a stored value that no longer parses is a storage problem too
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> Self {
+        AppError::Storage(err.to_string())
+    }
+}
+
+//as are rows of the database that do not make a valid index
+impl From<ontariopublic::DataError> for AppError {
+    fn from(err: ontariopublic::DataError) -> Self {
+        AppError::Storage(err.to_string())
+    }
+}
+
+//a template that fails to render is our bug, not the client's
+impl From<vaxrender::RenderError> for AppError {
+    fn from(err: vaxrender::RenderError) -> Self {
+        AppError::Render(err.to_string())
+    }
+}
+
+impl AppError {
+    pub fn status(&self) -> u16 {
+        match self {
+            AppError::NotFound { .. } => 404,
+            AppError::BadRequest(_) => 400,
+            AppError::Storage(_) => 503,
+            AppError::Render(_) => 500,
+        }
+    }
+
+    pub fn page(&self) -> ErrorPage {
+        match self {
+            AppError::NotFound { .. } => ErrorPage::NotFound,
+            AppError::BadRequest(_) => ErrorPage::BadRequest,
+            AppError::Storage(_) | AppError::Render(_) => ErrorPage::Unavailable,
+        }
+    }
+
+    pub fn nearest(&self) -> Option<&str> {
+        match self {
+            AppError::NotFound { nearest, .. } => nearest.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn body(&self) -> ErrorBody<'_> {
+        ErrorBody {
+            status: self.status(),
+            error: self.to_string(),
+            nearest: self.nearest(),
+        }
+    }
+}
@@ -1,10 +1,41 @@
-use num_format::{Locale, ToFormattedString};
-use ontariopublic::{DayReport, Index};
-use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use chrono::NaiveDate;
+use ontariopublic::{
+    day_key, decode, key_date, DayReport, Index, Outcome, Phu, Province, RegionReport, ONTARIO,
+    SERIES,
+};
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use vaxrender::{
+    render_calendar_str, render_chart_str, render_compare_str, render_detail_report_str,
+    render_error_page_str, render_feed_str, render_region_str, render_report_str, Headline, Host,
+    Lang, RenderResult, CSS,
+};
 use worker::*;
 
-//how long in seconds to cache key value store get results for
-const TTL_CACHE: u64 = 60;
+mod error;
+mod store;
+
+pub use error::{AppError, AppResult};
+use store::Store;
+
+//pages that change with every import
+const CACHE_LATEST: &str = "public, max-age=180";
+//days before the most recent one rarely change but their day pickers, navigation and updated
+//footer follow the index, so they can't be immutable: kept an hour, then revalidated against
+//the etag which is a 304 until the next import
+const CACHE_HISTORICAL: &str = "public, max-age=3600";
+//the stylesheet only changes with a new deployment
+const CACHE_STATIC: &str = "public, max-age=86400";
+
+//routes take plain fns returning worker results, this adapts views failing with AppError
+macro_rules! view {
+    ($view:ident) => {
+        |req, ctx| async move {
+            let res = $view(&req, &ctx).await;
+            respond(&req, res)
+        }
+    };
+}
 
 fn log_request(req: &Request) {
     console_log!(
@@ -17,9 +48,22 @@ fn log_request(req: &Request) {
 }
 
 #[event(fetch)]
-pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
+pub async fn main(req: Request, env: Env, ctx: Context) -> Result<Response> {
     log_request(&req);
 
+    //repeat hits are answered from the edge cache without touching the key value store
+    let cache = Cache::default();
+    let cache_key = cache_key(&req)?;
+    let if_none_match = req.headers().get("if-none-match")?;
+    if let Some(key) = &cache_key {
+        if let Some(cached) = cache.get(key.as_str(), false).await? {
+            return match cached_not_modified(&cached, if_none_match.as_deref())? {
+                Some(not_modified) => Ok(not_modified),
+                None => Ok(cached),
+            };
+        }
+    }
+
     // Optionally, use the Router to handle matching endpoints, use ":name" placeholders, or "*name"
     // catch-alls to match on specific patterns. Alternatively, use `Router::with_data(D)` to
     // provide arbitrary data that will be accessible in each route via the `ctx.data()` method.
@@ -29,13 +73,57 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     // functionality and a `RouteContext` which you can use to  and get route parameters and
     // Environment bindings like KV Stores, Durable Objects, Secrets, and Variables.
     let res = router
-        .get_async("/", index_view)
-        .get_async("/d/:date/", day_view)
-        .get_async("/dd/:date/", day_detail_view)
-        .get_async("/di/:idx/", idx_view)
-        .get_async("/ch/ca/", chart_cases_view)
-        .get_async("/ch/ni/", chart_nonicu_view)
-        .get_async("/ch/ii/", chart_icu_view)
+        .get_async("/", view!(index_view))
+        .get_async("/d/", view!(date_lookup_view))
+        .get_async("/d/:date/", view!(day_view))
+        .get_async("/dd/:date/", view!(day_detail_view))
+        .get_async("/di/:idx/", view!(idx_view))
+        .get_async("/di/", view!(idx_view))
+        .get_async("/ch/:chart/", view!(chart_view))
+        .get_async("/calendar/", view!(calendar_view))
+        .get_async("/cmp/", view!(compare_lookup_view))
+        .get_async("/cmp/:date1/:date2/", view!(compare_view))
+        .get_async("/r/", view!(region_lookup_view))
+        .get_async("/r/:phu/", view!(region_view))
+        .get_async("/r/:phu/d/:date/", view!(region_day_view))
+        .get_async("/fr/", view!(index_view))
+        .get_async("/fr/d/", view!(date_lookup_view))
+        .get_async("/fr/d/:date/", view!(day_view))
+        .get_async("/fr/dd/:date/", view!(day_detail_view))
+        .get_async("/fr/di/:idx/", view!(idx_view))
+        .get_async("/fr/di/", view!(idx_view))
+        .get_async("/fr/ch/:chart/", view!(chart_view))
+        .get_async("/fr/calendar/", view!(calendar_view))
+        .get_async("/fr/cmp/", view!(compare_lookup_view))
+        .get_async("/fr/cmp/:date1/:date2/", view!(compare_view))
+        .get_async("/fr/r/", view!(region_lookup_view))
+        .get_async("/fr/r/:phu/", view!(region_view))
+        .get_async("/fr/r/:phu/d/:date/", view!(region_day_view))
+        .get_async("/feed.atom", view!(feed_view))
+        .get_async("/fr/feed.atom", view!(feed_view))
+        //the other provinces and territories, ontario stays at the root
+        .get_async("/p/:prov/", view!(index_view))
+        .get_async("/p/:prov/d/", view!(date_lookup_view))
+        .get_async("/p/:prov/d/:date/", view!(day_view))
+        .get_async("/p/:prov/dd/:date/", view!(day_detail_view))
+        .get_async("/p/:prov/di/:idx/", view!(idx_view))
+        .get_async("/p/:prov/di/", view!(idx_view))
+        .get_async("/p/:prov/ch/:chart/", view!(chart_view))
+        .get_async("/p/:prov/calendar/", view!(calendar_view))
+        .get_async("/p/:prov/cmp/", view!(compare_lookup_view))
+        .get_async("/p/:prov/cmp/:date1/:date2/", view!(compare_view))
+        .get_async("/fr/p/:prov/", view!(index_view))
+        .get_async("/fr/p/:prov/d/", view!(date_lookup_view))
+        .get_async("/fr/p/:prov/d/:date/", view!(day_view))
+        .get_async("/fr/p/:prov/dd/:date/", view!(day_detail_view))
+        .get_async("/fr/p/:prov/di/:idx/", view!(idx_view))
+        .get_async("/fr/p/:prov/di/", view!(idx_view))
+        .get_async("/fr/p/:prov/ch/:chart/", view!(chart_view))
+        .get_async("/fr/p/:prov/calendar/", view!(calendar_view))
+        .get_async("/fr/p/:prov/cmp/", view!(compare_lookup_view))
+        .get_async("/fr/p/:prov/cmp/:date1/:date2/", view!(compare_view))
+        .get_async("/p/:prov/feed.atom", view!(feed_view))
+        .get_async("/fr/p/:prov/feed.atom", view!(feed_view))
         .get("/style.css", css_view)
         .get("/worker-version", |_, ctx| {
             let version = ctx.var("WORKERS_RS_VERSION")?.to_string();
@@ -45,7 +133,20 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
         .await;
     // evaluate how our router did - were there any errors - if so log them
     match res {
-        Ok(response) => Ok(response),
+        Ok(mut response) => {
+            //stored after the response went out, a failed put only costs the next hit
+            if let Some(key) = cache_key {
+                if response.status_code() == 200 {
+                    let cached = response.cloned()?;
+                    ctx.wait_until(async move {
+                        if let Err(err) = Cache::default().put(key.as_str(), cached).await {
+                            console_log!("cache put error => {}", err);
+                        }
+                    });
+                }
+            }
+            Ok(response)
+        }
         Err(err) => {
             console_log!("main error => {} | {:?}", err, err);
             Response::error("Sorry, there are some technical difficulties.", 500)
@@ -53,754 +154,545 @@ pub async fn main(req: Request, env: Env, _ctx: Context) -> Result<Response> {
     }
 }
 
-static SIMPLETOP: &str = r#"<!DOCTYPE html>
-<html lang="en">
-  <head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <meta http-equiv="X-UA-Compatible" content="ie=edge">
-    <title>vax.labath.ca</title>
-    <link rel="stylesheet" href="/style.css">"#;
-
-static BOTTOM: &str = r#"</body></html>"#;
-
-fn dec_to_string(d: Decimal) -> String {
-    d.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
-        .to_string()
-}
-
-fn human_string(d: i64) -> String {
-    d.to_formatted_string(&Locale::en)
-}
-
-async fn index_view(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let kv = ctx.kv("VAXKV")?;
-    let index = get_index(&kv).await?;
-    let report = get_report(&kv, &index.most_recent()).await?;
-    let body = render_report_str(&index, &report);
-    craft_response("text/html", &body)
-}
-
-pub fn render_report_str(index: &Index, report: &DayReport) -> String {
-    let date = report.cases.date.format("%A, %-d %B, %C%y").to_string();
-    let updated = index.updated.to_rfc2822();
-    let inf_rate_unvax = report
-        .cases
-        .cases_unvac_rate_per100k
-        .map_or_else(|| String::from("n/a"), dec_to_string);
-    let inf_rate_lt_2vax = report
-        .cases
-        .cases_notfull_vac_rate_per100k
-        .map_or_else(|| String::from("n/a"), dec_to_string);
-    let inf_rate_2vax = dec_to_string(report.cases.cases_full_vac_rate_per100k);
-    let icu_rate_unvax = report
-        .icu_unvac_rate_per100k()
-        .map_or_else(|| String::from("n/a"), dec_to_string);
-    let icu_rate_lt_2vax = report
-        .icu_notfull_vac_rate_per100k()
-        .map_or_else(|| String::from("n/a"), dec_to_string);
-    let icu_rate_2vax = dec_to_string(report.icu_full_vac_rate_per100k());
-    let hosp_rate_unvax = report
-        .nonicu_unvac_rate_per100k()
-        .map_or_else(|| String::from("n/a"), dec_to_string);
-    let hosp_rate_2vax = dec_to_string(report.nonicu_full_vac_rate_per100k());
-    let hosp_rate_lt_2vax = report
-        .nonicu_notfull_vac_rate_per100k()
-        .map_or_else(|| String::from("n/a"), dec_to_string);
-    let max_idx = index.max_idx();
-    let idx = index.idx(report.key()).unwrap_or_else(|| index.max_idx());
-    let cur_key = report.key();
-    let prev = match index.prev(report.key()) {
-        Some(prev) => {
-            let mut s = String::from("<A HREF=\"/d/");
-            s.push_str(&prev);
-            s.push_str("/\">Previous</A>");
-            s
+//only GET requests are cached, the home page is keyed by the negotiated language
+//since the edge cache does not honour vary
+fn cache_key(req: &Request) -> Result<Option<String>> {
+    if req.method() != Method::Get {
+        return Ok(None);
+    }
+    let mut url = req.url()?;
+    if url.path() == "/" {
+        let lang = req
+            .headers()
+            .get("accept-language")?
+            .map_or(Lang::En, |h| Lang::from_accept_language(&h));
+        url.query_pairs_mut().append_pair("lang", lang.code());
+    }
+    Ok(Some(url.to_string()))
+}
+
+fn cached_not_modified(cached: &Response, if_none_match: Option<&str>) -> Result<Option<Response>> {
+    match cached.headers().get("etag")? {
+        Some(etag) if etag_matches(if_none_match, &etag) => {
+            let cache_control = cached.headers().get("cache-control")?.unwrap_or_default();
+            Ok(Some(not_modified(&etag, &cache_control)?))
         }
-        None => "".to_string(),
-    };
-    let next = match index.next(report.key()) {
-        Some(next) => {
-            let mut s = String::from("<A HREF=\"/d/");
-            s.push_str(&next);
-            s.push_str("/\">Next</A>");
-            s
+        _ => Ok(None),
+    }
+}
+
+//if-none-match may carry a list of tags or * and weak tags compare equal to strong ones
+fn etag_matches(if_none_match: Option<&str>, etag: &str) -> bool {
+    let strip = |t: &str| t.trim().trim_start_matches("W/").to_string();
+    if_none_match.map_or(false, |header| {
+        header
+            .split(',')
+            .any(|t| t.trim() == "*" || strip(t) == strip(etag))
+    })
+}
+
+//fnv-1a, stable across builds unlike the std hasher
+fn etag(parts: &[&[u8]]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in parts {
+        for &b in part.iter().chain(&[0]) {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x100000001b3);
         }
-        None => "".to_string(),
+    }
+    format!("\"{:016x}\"", hash)
+}
+
+//tag for pages that only change when import publishes a new index
+fn index_etag(index: &Index, lang: Lang, page: &str) -> String {
+    etag(&[
+        index.updated.to_rfc3339().as_bytes(),
+        lang.code().as_bytes(),
+        page.as_bytes(),
+    ])
+}
+
+fn not_modified(etag: &str, cache_control: &str) -> AppResult<Response> {
+    let mut resp = Response::empty()?.with_status(304);
+    let headers = resp.headers_mut();
+    headers.set("etag", etag)?;
+    headers.set("cache-control", cache_control)?;
+    Ok(resp)
+}
+
+//answers 304 when the browser already has the page, otherwise renders it
+fn conditional_response(
+    req: &Request,
+    etag: &str,
+    cache_control: &str,
+    content_type: &str,
+    render: impl FnOnce() -> RenderResult,
+) -> AppResult<Response> {
+    if etag_matches(req.headers().get("if-none-match")?.as_deref(), etag) {
+        return not_modified(etag, cache_control);
+    }
+    craft_response(content_type, &render()?, etag, cache_control)
+}
+
+impl From<worker::Error> for AppError {
+    fn from(err: worker::Error) -> Self {
+        AppError::Storage(err.to_string())
+    }
+}
+
+impl From<AppError> for worker::Error {
+    fn from(err: AppError) -> Self {
+        worker::Error::RustError(err.to_string())
+    }
+}
+
+//errors become an html page, or json for api clients that do not accept html
+fn respond(req: &Request, res: AppResult<Response>) -> Result<Response> {
+    let err = match res {
+        Ok(resp) => return Ok(resp),
+        Err(err) => err,
     };
-    format!(
-        r#"{SIMPLETOP}
-    <script>
-      window.onload = (event) => {{
-        var slider = document.getElementById("dayRange");
-        slider.onchange = (event) => {{
-          let url = "/di/"+slider.value+"/";
-          window.location.assign(url);
-        }};
-      }};
-    </script>
-  </head>
-<body>
-<h3>Report for {date}</h3>
-<h3>COVID-19 per capita comparison by vaccination status in Ontario, Canada.</h3>
-<div><a href="/dd/{cur_key}/">Click here for detailed report</a></div>
-<div id="main">
-<table>
-  <tr>
-    <td>Rate per 100,000</td>
-    <td>0 doses</td>
-    <td>&lt; 2 doses</td>
-    <td>2 doses</td>
-  </tr>
-  <tr>
-    <td><a href="/ch/ca/">Tested positive</a></td>
-    <th>{inf_rate_unvax}</th>
-    <th>{inf_rate_lt_2vax}</th>
-    <th>{inf_rate_2vax}</th>
-  </tr>
-  <tr>
-    <td><a href="/ch/ni/">Hospitalized not in ICU</a></td>
-    <th>{hosp_rate_unvax}</th>
-    <th>{hosp_rate_lt_2vax}</th>
-    <th>{hosp_rate_2vax}</th>
-  </tr>
-  <tr>
-    <td><a href="/ch/ii/">Hospitalized in ICU</a></td>
-    <th>{icu_rate_unvax}</th>
-    <th>{icu_rate_lt_2vax}</th>
-    <th>{icu_rate_2vax}</th>
-  </tr>
-</table>
-</div>
-<div class="slidecontainer">
-  <input type="range" min="0" max="{max_idx}" value="{idx}" class="slider" id="dayRange">
-</div>
-<div id="nav_buttons">
-{prev}
-{next}
-</div>
-<div id="footer"><a href="https://github.com/jlabath/vax">source code</a></div>
-<div id="updated"><h5>Ontario government: Please note that Cases by Vaccination Status and Hospitalization by Vaccination Status data will no longer be published as of June 23, 2022.<br>Updated: {updated}</h5></div>
-{BOTTOM}
-"#
-    )
-}
-
-pub fn render_detail_report_str(index: &Index, report: &DayReport) -> String {
-    let date = report.cases.date.format("%A, %-d %B, %C%y").to_string();
-    let updated = index.updated.to_rfc2822();
-    let inf_rate_unvax = report
-        .cases
-        .cases_unvac_rate_per100k
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_notfull_vax = report
-        .cases
-        .cases_notfull_vac_rate_per100k
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_2vax = dec_to_string(report.cases.cases_full_vac_rate_per100k);
-    let inf_rate_1vax = report
-        .cases
-        .cases_partial_vac_rate_per100k
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_boost_vax = report
-        .cases
-        .cases_boost_vac_rate_per100k
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_unvax_ma = report
-        .cases
-        .cases_unvac_rate_7ma
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_2vax_ma = report
-        .cases
-        .cases_full_vac_rate_7ma
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_1vax_ma = report
-        .cases
-        .cases_partial_vac_rate_7ma
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_notfull_ma = report
-        .cases
-        .cases_notfull_vac_rate_7ma
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let inf_rate_boost_ma = report
-        .cases
-        .cases_boost_vac_rate_7ma
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let icu_rate_unvax = report
-        .icu_unvac_rate_per100k()
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let icu_rate_1vax = report
-        .icu_partial_vac_rate_per100k()
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let icu_rate_lt_2vax = report
-        .icu_notfull_vac_rate_per100k()
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let icu_rate_2vax = dec_to_string(report.icu_full_vac_rate_per100k());
-    let hosp_rate_unvax = report
-        .nonicu_unvac_rate_per100k()
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let hosp_rate_1vax = report
-        .nonicu_partial_vac_rate_per100k()
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let hosp_rate_lt_2vax = report
-        .nonicu_notfull_vac_rate_per100k()
-        .map_or_else(|| String::from("N/A"), dec_to_string);
-    let hosp_rate_2vax = dec_to_string(report.nonicu_full_vac_rate_per100k());
-    let cases_unvax = report
-        .cases
-        .covid19_cases_unvac
-        .map_or_else(|| String::from("N/A"), human_string);
-    let cases_partial_vax = report
-        .cases
-        .covid19_cases_partial_vac
-        .map_or_else(|| String::from("N/A"), human_string);
-    let cases_notfull_vax = report
-        .cases
-        .covid19_cases_notfull_vac
-        .map_or_else(|| String::from("N/A"), human_string);
-    let cases_full_vax = human_string(report.cases.covid19_cases_full_vac);
-    let cases_boost_vax = report
-        .cases
-        .covid19_cases_boost_vac
-        .map_or_else(|| String::from("N/A"), human_string);
-    let cases_unknown_vax = report
-        .cases
-        .covid19_cases_vac_unknown
-        .map_or_else(|| String::from("N/A"), human_string);
-    let icu_unvax = human_string(report.hosps.icu_unvac);
-    let icu_1vax = human_string(report.hosps.icu_partial_vac);
-    let icu_2vax = human_string(report.hosps.icu_full_vac);
-    let hosp_unvax = human_string(report.hosps.hospitalnonicu_unvac);
-    let hosp_1vax = human_string(report.hosps.hospitalnonicu_partial_vac);
-    let hosp_2vax = human_string(report.hosps.hospitalnonicu_full_vac);
-    let pop_unvax = report.cases.calc_unvac_population().map_or_else(
-        || String::from("N/A"),
-        |v| {
-            human_string(
-                v.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
-                    .to_i64()
-                    .unwrap_or(0),
-            )
-        },
-    );
-    let pop_1vax = report.cases.calc_partial_vac_population().map_or_else(
-        || String::from("N/A"),
-        |v| {
-            human_string(
-                v.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
-                    .to_i64()
-                    .unwrap_or(0),
-            )
-        },
-    );
-    let pop_lt_2vax = report.cases.calc_notfull_vac_population().map_or_else(
-        || String::from("N/A"),
-        |v| {
-            human_string(
-                v.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
-                    .to_i64()
-                    .unwrap_or(0),
-            )
-        },
-    );
-    let pop_2vax = human_string(
-        report
-            .cases
-            .calc_full_vac_population()
-            .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
-            .to_i64()
-            .unwrap_or(0),
-    );
-    let pop_3vax = report.cases.calc_boost_vac_population().map_or_else(
-        || String::from("N/A"),
-        |v| {
-            human_string(
-                v.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
-                    .to_i64()
-                    .unwrap_or(0),
-            )
-        },
-    );
-    let prev = match index.prev(report.key()) {
-        Some(prev) => {
-            let mut s = String::from("<A HREF=\"/dd/");
-            s.push_str(&prev);
-            s.push_str("/\">Previous</A>");
-            s
+    let path = req.path();
+    console_log!("{} error => {} | {:?}", path, err, err);
+    let wants_json = req.headers().get("accept")?.map_or(false, |a| {
+        a.contains("application/json") && !a.contains("text/html")
+    });
+    let resp = if wants_json {
+        Response::from_json(&err.body())?
+    } else {
+        let lang = Lang::from_path(&path);
+        let bare = path.strip_prefix(lang.prefix()).unwrap_or(&path);
+        let (province, bare) = Province::from_path(bare);
+        //the error page failing too leaves plain text, the status still tells what happened
+        match render_error_page_str(err.page(), err.nearest(), province, bare, lang) {
+            Ok(page) => Response::from_html(page)?,
+            Err(page_err) => Response::ok(format!("{}\n{}", err, page_err))?,
         }
-        None => "".to_string(),
     };
-    let next = match index.next(report.key()) {
-        Some(next) => {
-            let mut s = String::from("<A HREF=\"/dd/");
-            s.push_str(&next);
-            s.push_str("/\">Next</A>");
-            s
-        }
-        None => "".to_string(),
+    let mut resp = resp.with_status(err.status());
+    let headers = resp.headers_mut();
+    headers.set("cache-control", "no-store")?;
+    if let AppError::Storage(_) = err {
+        headers.set("retry-after", "60")?;
+    }
+    Ok(resp)
+}
+
+//day keys in urls must be dates the index has a report for
+fn known_key(index: &Index, key: &str) -> AppResult<()> {
+    match key_date(key) {
+        None => Err(AppError::BadRequest(format!("`{}` is not a date", key))),
+        Some(day) if index.contains(day) => Ok(()),
+        Some(day) => Err(AppError::NotFound {
+            key: key.to_string(),
+            nearest: index.nearest(day).map(day_key),
+        }),
+    }
+}
+
+//the key of the most recent day, import never stores an index without days
+fn latest_key(index: &Index) -> AppResult<String> {
+    index
+        .last()
+        .map(day_key)
+        .ok_or_else(|| AppError::Storage("the index is empty".into()))
+}
+
+//the /fr/ prefix picks the language, only the home page falls back to Accept-Language
+fn request_lang(req: &Request) -> Lang {
+    Lang::from_path(&req.path())
+}
+
+//routes under /p/:prov/ are another province, the others are ontario's
+//which is only served at the root so its pages never link out of /p/on/
+fn request_province(ctx: &RouteContext<()>) -> AppResult<&'static Province> {
+    match ctx.param("prov") {
+        Some(code) => Province::by_code(code)
+            .filter(|p| !p.is_ontario())
+            .ok_or_else(|| AppError::NotFound {
+                key: code.to_string(),
+                nearest: None,
+            }),
+        None => Ok(ONTARIO),
+    }
+}
+
+async fn index_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let path = req.path();
+    let lang = match path.as_str() {
+        "/" => req
+            .headers()
+            .get("accept-language")?
+            .map_or(Lang::En, |h| Lang::from_accept_language(&h)),
+        _ => Lang::from_path(&path),
     };
-    let cur_key = report.key();
-    format!(
-        r#"{SIMPLETOP}
-  </head>
-<body>
-<h3>Detailed report for {date}, Ontario, Canada.</h3>
-<div>
-<a href="/d/{cur_key}/">Back to compare view</a>
-<h3>COVID-19 cases by vaccination status</h3>
-<table>
-  <tr>
-    <td class="label">Unvaccinated</td>
-    <td class="num">{cases_unvax}</td>
-    <td>Number of people who tested positive for COVID-19 on this date. Individuals are considered unvaccinated if they have not had a dose, or if their first dose was less than fourteen days ago.</td>
-  </tr>
-  <tr>
-    <td class="label">Partially Vaccinated</td>
-    <td class="num">{cases_partial_vax}</td>
-    <td>Number of people who tested positive for COVID-19 on this date.  Individuals are considered partially vaccinated if they have had one dose at least fourteen days ago, or two doses where the second dose was less than fourteen days ago.</td>
-  </tr>
-  <tr>
-    <td class="label">Not fully vaccinated (less than 2 doses)</td>
-    <td class="num">{cases_notfull_vax}</td>
-    <td>Number of not fully vaccinated people who tested positive for COVID-19 on this date. Individuals are considered fully vaccinated if they have had two doses and the second dose was at least fourteen days ago. New datapoint as of March 11, 2022.</td>
-  </tr>
-  <tr>
-    <td class="label">Fully vaccinated</td>
-    <td class="num">{cases_full_vax}</td>
-    <td>Number of people who tested positive for COVID-19 on this date. Individuals are considered fully vaccinated if they have had two doses and the second dose was at least fourteen days ago.</td>
-  </tr>
-  <tr>
-    <td class="label">Boosted (3 doses)</td>
-    <td class="num">{cases_boost_vax}</td>
-    <td>Number of boosted people who tested positive for COVID-19 on this date. Individuals are considered fully vaccinated if they have had three doses and the third dose was at least fourteen days ago. New datapoint as of March 11, 2022.</td>
-  </tr>
-  <tr>
-    <td class="label">Unknown vaccination status</td>
-    <td class="num">{cases_unknown_vax}</td>
-    <td>Number of people who tested positive for COVID-19 on this date, but their vaccination status is unknown.</td>
-  </tr>
-  <tr>
-    <td class="label">Unvaccinated rate per 100,000</td>
-    <td class="num">{inf_rate_unvax}</td>
-    <td>Rate of COVID-19 cases per 100,000 of unvaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000).</td>
-  </tr>
-  <tr>
-    <td class="label">Partially vaccinated rate per 100,000</td>
-    <td class="num">{inf_rate_1vax}</td>
-    <td>Rate of COVID-19 cases per 100,000 of partially vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000).</td>
-  </tr>
-  <tr>
-    <td class="label">Not fully vaccinated rate per 100,000</td>
-    <td class="num">{inf_rate_notfull_vax}</td>
-    <td>Rate of COVID-19 cases per 100,000 of not fully vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000). New as of March 11, 2022.</td>
-  </tr>
-  <tr>
-    <td class="label">Fully vaccinated rate per 100,000</td>
-    <td class="num">{inf_rate_2vax}</td>
-    <td>Rate of COVID-19 cases per 100,000 of fully vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000).</td>
-  </tr>
-  <tr>
-    <td class="label">Boosted rate per 100,000</td>
-    <td class="num">{inf_rate_boost_vax}</td>
-    <td>Rate of COVID-19 cases per 100,000 of fully vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000). New as of March 11, 2022.</td>
-  </tr>
-  <tr>
-    <td class="label">Unvaccinated rate per 100,000 (7 day moving average)</td>
-    <td class="num">{inf_rate_unvax_ma}</td>
-    <td>The average rate of COVID-19 cases per 100,000 for the previous 7 days for this vaccination status.</td>
-  </tr>
-  <tr>
-    <td class="label">Partially vaccinated rate per 100,000 (7 day moving average)</td>
-    <td class="num">{inf_rate_1vax_ma}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Not fully vaccinated rate per 100,000 (7 day moving average)</td>
-    <td class="num">{inf_rate_notfull_ma}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Fully vaccinated rate per 100,000 (7 day moving average)</td>
-    <td class="num">{inf_rate_2vax_ma}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Boosted rate per 100,000 (7 day moving average)</td>
-    <td class="num">{inf_rate_boost_ma}</td>
-    <td>&#x3003;</td>
-  </tr>
-</table>
-<h3>COVID-19 hospitalizations by vaccination status</h3>
-<h5>Due to incomplete weekend and holiday reporting, vaccination status data for hospital and ICU admissions is not updated on Sundays, Mondays and the day after holidays.</h5>
-<table>
- <tr>
-    <td class="label">Unvaccinated hospitalized but not in ICU</td>
-    <td class="num">{hosp_unvax}</td>
-    <td>Number of people admitted to a hospital but not requiring a stay in ICU. In order to understand the vaccination status of patients currently hospitalized, a new data collection process was developed and this may cause discrepancies between other hospitalization numbers being collected using a different data collection process.</td>
-  </tr>
- <tr>
-    <td class="label">Partially vaccinated hospitalized but not in ICU</td>
-    <td class="num">{hosp_1vax}</td>
-    <td>&#x3003;</td>
-  </tr>
- <tr>
-    <td class="label">Fully vaccinated hospitalized but not in ICU</td>
-    <td class="num">{hosp_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Unvaccinated in ICU</td>
-    <td class="num">{icu_unvax}</td>
-    <td>Number of people hospitalized in ICU with COVID-19. Data on patients in ICU are being collected from two different data sources with different extraction times and public reporting cycles. The existing data source (Critical Care Information System, CCIS) does not have vaccination status.</td>
-  </tr>
- <tr>
-    <td class="label">Partially vaccinated in ICU</td>
-    <td class="num">{icu_1vax}</td>
-    <td>&#x3003;</td>
-  </tr>
- <tr>
-    <td class="label">Fully vaccinated in ICU</td>
-    <td class="num">{icu_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-</table>
-<h3>Computed metrics based on government data above</h3>
-<h5>The per 100,000 scale is used when the more commonly used per cent (per hundred) scale would result in very small decimal numbers. E.g. 1 in 100,000 equals 0.001%, or 1000 in 100,000 equals 1% of the population.</h5>
-<table>
-  <tr>
-    <td class="label">Number of unvaccinated people in Ontario</td>
-    <td class="num">{pop_unvax}</td>
-    <td>Calculated as case count for this vaccination status times 100,000 and then divided by rate and rounded.</td>
-  </tr>
-  <tr>
-    <td class="label">Number of partially vaccinated people in Ontario</td>
-    <td class="num">{pop_1vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Number of not fully vaccinated people in Ontario (less than 2 doses)</td>
-    <td class="num">{pop_lt_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Number of fully vaccinated people in Ontario</td>
-    <td class="num">{pop_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Number of boosted people in Ontario</td>
-    <td class="num">{pop_3vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Non ICU hospitalization rate of unvaccinated per 100,000</td>
-    <td class="num">{hosp_rate_unvax}</td>
-    <td>Calculated as number of non ICU hospitalizations for this vaccination status times 100,000 and then divided by the population for this vaccination status.</td>
-  </tr>
-  <tr>
-    <td class="label">Non ICU hospitalization rate of partially vaccinated per 100,000</td>
-    <td class="num">{hosp_rate_1vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Non ICU hospitalization rate of not fully vaccinated per 100,000</td>
-    <td class="num">{hosp_rate_lt_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">Non ICU hospitalization rate of fully vaccinated per 100,000</td>
-    <td class="num">{hosp_rate_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">ICU hospitalization rate of unvaccinated per 100,000</td>
-    <td class="num">{icu_rate_unvax}</td>
-    <td>Calculated as number of ICU hospitalizations for this vaccination status times 100,000 and then divided by the population for this vaccination status.</td>
-  </tr>
-  <tr>
-    <td class="label">ICU hospitalization rate of partially vaccinated per 100,000</td>
-    <td class="num">{icu_rate_1vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">ICU hospitalization rate of not fully vaccinated per 100,000</td>
-    <td class="num">{icu_rate_lt_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-  <tr>
-    <td class="label">ICU hospitalization rate of fully vaccinated per 100,000</td>
-    <td class="num">{icu_rate_2vax}</td>
-    <td>&#x3003;</td>
-  </tr>
-</table>
-<a href="/d/{cur_key}/">Back to compare view</a>
-</div>
-<div id="nav_buttons">
-{prev}
-{next}
-</div>
-<div id="footer"><a href="https://github.com/jlabath/vax">source code</a></div>
-<div id="updated"><h5>Ontario government: Please note that Cases by Vaccination Status and Hospitalization by Vaccination Status data will no longer be published as of June 23, 2022.<br>Updated: {updated}</h5></div>
-{BOTTOM}
-"#
-    )
-}
-
-async fn get_index(kv: &kv::KvStore) -> Result<Index> {
-    let index_opt = kv.get("index").cache_ttl(TTL_CACHE).json().await?;
-    index_opt.ok_or_else(|| "Could not fetch index".into())
-}
-
-async fn get_report(kv: &kv::KvStore, key: &str) -> Result<DayReport> {
-    let opt = kv.get(key).cache_ttl(TTL_CACHE).json().await?;
-    opt.ok_or_else(|| {
-        let mut msg = String::from("Could not fetch report: ");
-        msg.push_str(key);
-        msg.into()
-    })
+    let province = request_province(ctx)?;
+    let store = Store::open(ctx, province).await?;
+    let index = store.index(province).await?;
+    let etag = index_etag(&index, lang, "index");
+    let mut resp = if etag_matches(req.headers().get("if-none-match")?.as_deref(), &etag) {
+        not_modified(&etag, CACHE_LATEST)?
+    } else {
+        let report = store.report(province, &latest_key(&index)?).await?;
+        let body = render_report_str(&index, &report, lang, Host::Worker)?;
+        craft_response("text/html", &body, &etag, CACHE_LATEST)?
+    };
+    resp.headers_mut().set("vary", "Accept-Language")?;
+    Ok(resp)
+}
+
+//the most recent day changes with the next import through its navigation,
+//older days are tagged by their content, the range of the day pickers and the updated footer
+//raw loads the stored json of the report of key, it only runs when the page is rendered or tagged
+async fn report_response<R: DeserializeOwned>(
+    req: &Request,
+    index: &Index,
+    key: &str,
+    raw: impl Future<Output = AppResult<Vec<u8>>>,
+    page: &str,
+    render: impl Fn(&Index, &R, Lang) -> RenderResult,
+) -> AppResult<Response> {
+    let lang = request_lang(req);
+    if index.last().is_some_and(|d| day_key(d) == key) {
+        let etag = index_etag(index, lang, page);
+        if etag_matches(req.headers().get("if-none-match")?.as_deref(), &etag) {
+            return not_modified(&etag, CACHE_LATEST);
+        }
+        let report = decode(&raw.await?)?;
+        let body = render(index, &report, lang)?;
+        return craft_response("text/html", &body, &etag, CACHE_LATEST);
+    }
+    let raw = raw.await?;
+    let days = index.len().to_string();
+    let updated = index.updated.to_rfc3339();
+    let etag = etag(&[
+        raw.as_slice(),
+        days.as_bytes(),
+        updated.as_bytes(),
+        lang.code().as_bytes(),
+        page.as_bytes(),
+    ]);
+    if etag_matches(req.headers().get("if-none-match")?.as_deref(), &etag) {
+        return not_modified(&etag, CACHE_HISTORICAL);
+    }
+    let report: R = decode(&raw)?;
+    let body = render(index, &report, lang)?;
+    craft_response("text/html", &body, &etag, CACHE_HISTORICAL)
+}
+
+//report_response renders with a fn of the index, the report and the language
+fn render_day(index: &Index, report: &DayReport, lang: Lang) -> RenderResult {
+    render_report_str(index, report, lang, Host::Worker)
 }
 
-async fn day_view(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+async fn day_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
     match ctx.param("date") {
         Some(key) => {
-            let kv = ctx.kv("VAXKV")?;
-            let index = get_index(&kv).await?;
-            let report = get_report(&kv, key).await?;
-            let body = render_report_str(&index, &report);
-            craft_response("text/html", &body)
+            let province = request_province(ctx)?;
+            let store = Store::open(ctx, province).await?;
+            let index = store.index(province).await?;
+            known_key(&index, key)?;
+            let raw = store.report_raw(province, key);
+            report_response(req, &index, key, raw, "day", render_day).await
         }
-        None => Response::error("Bad Request", 400),
+        None => Err(AppError::BadRequest("missing date".into())),
+    }
+}
+
+//the date picker sends ?date=YYYY-MM-DD, redirect to the closest day we have a report for
+async fn date_lookup_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let lang = request_lang(req);
+    let date = req
+        .url()?
+        .query_pairs()
+        .find(|(k, _)| k == "date")
+        .and_then(|(_, v)| NaiveDate::parse_from_str(&v, "%Y-%m-%d").ok());
+    match date {
+        Some(date) => {
+            let province = request_province(ctx)?;
+            let store = Store::open(ctx, province).await?;
+            let index = store.index(province).await?;
+            match index.nearest(date).map(day_key) {
+                Some(key) => redirect(&format!(
+                    "{}{}/d/{}/",
+                    lang.prefix(),
+                    province.prefix(),
+                    key
+                )),
+                None => Err(AppError::NotFound {
+                    key: date.to_string(),
+                    nearest: None,
+                }),
+            }
+        }
+        None => Err(AppError::BadRequest("missing or invalid date".into())),
+    }
+}
+
+//the region selector sends ?phu=<slug>, the empty value is the whole province
+async fn region_lookup_view(req: &Request, _ctx: &RouteContext<()>) -> AppResult<Response> {
+    let lang = request_lang(req);
+    let slug = req
+        .url()?
+        .query_pairs()
+        .find(|(k, _)| k == "phu")
+        .map(|(_, v)| v.into_owned())
+        .unwrap_or_default();
+    if slug.is_empty() {
+        return redirect(&format!("{}/", lang.prefix()));
     }
+    let phu = known_phu(&slug)?;
+    redirect(&format!("{}/r/{}/", lang.prefix(), phu.slug))
 }
 
-async fn day_detail_view(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
+fn known_phu(slug: &str) -> AppResult<&'static Phu> {
+    Phu::by_slug(slug).ok_or_else(|| AppError::NotFound {
+        key: slug.to_string(),
+        nearest: None,
+    })
+}
+
+async fn region_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let phu = known_phu(ctx.param("phu").map_or("", |s| s.as_str()))?;
+    let store = Store::regions(ctx)?;
+    let index = store.region_index(phu).await?;
+    let key = latest_key(&index)?;
+    let render = |index: &Index, report: &RegionReport, lang| {
+        render_region_str(phu, index, report, lang, Host::Worker)
+    };
+    let page = format!("region {}", phu.slug);
+    let raw = store.region_report_raw(phu, &key);
+    report_response(req, &index, &key, raw, &page, render).await
+}
+
+async fn region_day_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let phu = known_phu(ctx.param("phu").map_or("", |s| s.as_str()))?;
     match ctx.param("date") {
         Some(key) => {
-            let kv = ctx.kv("VAXKV")?;
-            let index = get_index(&kv).await?;
-            let report = get_report(&kv, key).await?;
-            let body = render_detail_report_str(&index, &report);
-            craft_response("text/html", &body)
+            let store = Store::regions(ctx)?;
+            let index = store.region_index(phu).await?;
+            //the nearest day of the error page links to the provincial reports
+            known_key(&index, key).map_err(|err| match err {
+                AppError::NotFound { key, .. } => AppError::NotFound { key, nearest: None },
+                err => err,
+            })?;
+            let render = |index: &Index, report: &RegionReport, lang| {
+                render_region_str(phu, index, report, lang, Host::Worker)
+            };
+            let page = format!("region {}", phu.slug);
+            let raw = store.region_report_raw(phu, key);
+            report_response(req, &index, key, raw, &page, render).await
         }
-        None => Response::error("Bad Request", 400),
+        None => Err(AppError::BadRequest("missing date".into())),
     }
 }
 
-async fn idx_view(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    match ctx.param("idx") {
-        Some(sidx) => {
-            let kv = ctx.kv("VAXKV")?;
-            let index = get_index(&kv).await?;
-            if let Ok(idx) = sidx.parse::<usize>() {
-                let key = match index.get(idx) {
-                    Some(k) => k,
-                    None => "fail".to_string(),
-                };
-                let report = get_report(&kv, &key).await?;
-                let body = render_report_str(&index, &report);
-                craft_response("text/html", &body)
-            } else {
-                Response::error("Bad Request", 400)
+async fn compare_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let lang = request_lang(req);
+    match (ctx.param("date1"), ctx.param("date2")) {
+        (Some(first_key), Some(second_key)) => {
+            let province = request_province(ctx)?;
+            let store = Store::open(ctx, province).await?;
+            let index = store.index(province).await?;
+            known_key(&index, first_key)?;
+            known_key(&index, second_key)?;
+            let etag = index_etag(&index, lang, &format!("cmp {} {}", first_key, second_key));
+            if etag_matches(req.headers().get("if-none-match")?.as_deref(), &etag) {
+                return not_modified(&etag, CACHE_LATEST);
             }
+            let first = store.report(province, first_key).await?;
+            let second = store.report(province, second_key).await?;
+            let body = render_compare_str(&index, &first, &second, lang, Host::Worker)?;
+            craft_response("text/html", &body, &etag, CACHE_LATEST)
         }
-        None => Response::error("Bad Request", 400),
+        _ => Err(AppError::BadRequest("missing dates".into())),
     }
 }
 
-static CSS: &str = r#"
-body {
-  background-color: white;
-  padding-left: 1em;
-}
-table, th, td {
-  border: 1px solid black;
-}
-table {
-  border-collapse: collapse;
-}
-td, th {
-  text-align: center;
-  padding: 1em;
-  vertical-align: middle;
-}
-td.label {
-  text-align: left;
-}
-td.num {
-  text-align: right;
-}
-.slidecontainer {
-  height: 4em;
-  /* Center vertically */
-  display: flex;
-  align-items: center;
-  width: 21em;
-}
-#nav_buttons {
-  height: 4em;
-  display: flex;
-  align-items: center;
-  justify-content: space-between;
-  width: 21em;
-}
-#footer {
-  height: 8em;
-  display: flex;
-  align-items: end;
-  width: 21em;
-  justify-content: center;
-}
-#updated {
-  height: 6em;
-  display: flex;
-  align-items: end;
-  width: 21em;
-  justify-content: center;
-}
-#dayRange {
-  width: 100%;
-}
-#main {
-  margin-top: 1em;
-}
-"#;
-
-fn css_view(_req: Request, _ctx: RouteContext<()>) -> Result<Response> {
-    craft_response("text/css", CSS)
-}
-
-async fn chart_cases_view(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let kv = ctx.kv("VAXKV")?;
-    let dose0 = get_kvval(&kv, "cases_dose0").await?;
-    let dose2 = get_kvval(&kv, "cases_dose2").await?;
-    let dose_lt2 = get_kvval(&kv, "cases_dose_lt2").await?;
-    let title =
-        "<h3>COVID-19 cases by vaccination status per 100,000 people in Ontario, Canada.</h3>";
-    chart_response(&kv, title, &dose0, &dose2, &dose_lt2).await
-}
-
-static CHART_JS: &str = r#"
-window.onload = (event) => {
-  const data = {
-    labels: labels,
-    datasets: [{
-      label: '0 doses',
-      data: dose0,
-      fill: false,
-      borderColor: 'red',
-      tension: 0.1
-    },{
-      label: '2 doses',
-      data: dose2,
-      fill: false,
-      borderColor: 'green',
-      tension: 0.1
-    },{
-      label: 'less than 2 doses',
-      data: dose_lt2,
-      fill: false,
-      borderColor: 'maroon',
-      tension: 0.1
-    }]
-  };
-  const config = {
-    type: 'line',
-    data: data,
-    options: {
-      responsive: true
+//the compare form sends ?a=YYYY-MM-DD&b=YYYY-MM-DD, snap both and redirect
+async fn compare_lookup_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let lang = request_lang(req);
+    let url = req.url()?;
+    let query_date = |name: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == name)
+            .and_then(|(_, v)| NaiveDate::parse_from_str(&v, "%Y-%m-%d").ok())
+    };
+    match (query_date("a"), query_date("b")) {
+        (Some(a), Some(b)) => {
+            let province = request_province(ctx)?;
+            let store = Store::open(ctx, province).await?;
+            let index = store.index(province).await?;
+            let first = index.nearest(a).map(day_key);
+            let second = index.nearest(b).map(day_key);
+            match (first, second) {
+                (Some(first), Some(second)) => redirect(&format!(
+                    "{}{}/cmp/{}/{}/",
+                    lang.prefix(),
+                    province.prefix(),
+                    first,
+                    second
+                )),
+                _ => Err(AppError::NotFound {
+                    key: format!("{} {}", a, b),
+                    nearest: None,
+                }),
+            }
+        }
+        _ => Err(AppError::BadRequest("missing or invalid dates".into())),
     }
-  };
-  const myChart = new Chart(
-    document.getElementById('myChart'),
-     config
-  );
-}
-"#;
-
-async fn chart_response(
-    kv: &kv::KvStore,
-    title: &str,
-    dose0: &str,
-    dose2: &str,
-    dose_lt2: &str,
-) -> Result<Response> {
-    let labels = get_kvval(kv, "labels").await?;
-    let mut body = String::with_capacity(1024 * 5); //5k
-    body.push_str(SIMPLETOP);
-    body.push_str(
-        r#"
-<script src="https://cdnjs.cloudflare.com/ajax/libs/Chart.js/3.7.0/chart.min.js"></script>
-<script>
-  const labels = "#,
-    );
-    body.push_str(&labels);
-    body.push_str(
-        r#";
-  const dose0 = "#,
-    );
-    body.push_str(dose0);
-    body.push_str(
-        r#";
-  const dose2 = "#,
-    );
-    body.push_str(dose2);
-    body.push(';');
-    body.push_str(
-        r#";
-  const dose_lt2 = "#,
-    );
-    body.push_str(dose_lt2);
-    body.push(';');
-    body.push_str(CHART_JS);
-    body.push_str("</script></head><body>");
-    body.push_str(title);
-    body.push_str(
-        r#"
-<div><a href="/" alt="home">&#8701; home</a></div>
-<div class="chart-container" style="position: relative; height:80vh; width:95vw">
-  <canvas id="myChart"></canvas>
-</div>
-"#,
-    );
-    body.push_str(BOTTOM);
-    craft_response("text/html", &body)
 }
 
-fn craft_response(content_type: &str, body: &str) -> Result<Response> {
+//the feed summaries come from the chart series so a few reads cover every day
+async fn feed_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let lang = request_lang(req);
+    let base = req.url()?.origin().ascii_serialization();
+    let province = request_province(ctx)?;
+    let store = Store::open(ctx, province).await?;
+    let index = store.index(province).await?;
+    let etag = index_etag(&index, lang, "feed");
+    if etag_matches(req.headers().get("if-none-match")?.as_deref(), &etag) {
+        return not_modified(&etag, CACHE_LATEST);
+    }
+    //SERIES is grouped by outcome so the charts in turn hold its series in order
+    let mut labels = vec![];
+    let mut series: Vec<Vec<Option<f64>>> = Vec::with_capacity(SERIES.len());
+    for outcome in Outcome::ALL {
+        let chart = store.chart(province, outcome, None, None).await?;
+        labels = chart.labels;
+        series.extend(chart.lines);
+    }
+    let headlines = Headline::from_series(&labels, &series);
+    let body = render_feed_str(&index, &headlines, &base, lang)?;
+    craft_response("application/atom+xml", &body, &etag, CACHE_LATEST)
+}
+
+async fn calendar_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let lang = request_lang(req);
+    let province = request_province(ctx)?;
+    let store = Store::open(ctx, province).await?;
+    let index = store.index(province).await?;
+    let etag = index_etag(&index, lang, "calendar");
+    conditional_response(req, &etag, CACHE_LATEST, "text/html", || {
+        render_calendar_str(&index, lang)
+    })
+}
+
+async fn day_detail_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    match ctx.param("date") {
+        Some(key) => {
+            let province = request_province(ctx)?;
+            let store = Store::open(ctx, province).await?;
+            let index = store.index(province).await?;
+            known_key(&index, key)?;
+            let raw = store.report_raw(province, key);
+            report_response(req, &index, key, raw, "detail", render_detail_report_str).await
+        }
+        None => Err(AppError::BadRequest("missing date".into())),
+    }
+}
+
+//the index comes either from the path or from the ?idx= of the day picker form
+async fn idx_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let sidx = match ctx.param("idx") {
+        Some(sidx) => Some(sidx.to_string()),
+        None => req
+            .url()?
+            .query_pairs()
+            .find(|(k, _)| k == "idx")
+            .map(|(_, v)| v.into_owned()),
+    };
+    match sidx {
+        Some(sidx) => {
+            let province = request_province(ctx)?;
+            let store = Store::open(ctx, province).await?;
+            let index = store.index(province).await?;
+            let idx = sidx
+                .parse::<usize>()
+                .map_err(|_| AppError::BadRequest(format!("`{}` is not a day number", sidx)))?;
+            //past the end the closest day is the most recent one
+            let key = index
+                .get(idx)
+                .map(day_key)
+                .ok_or_else(|| AppError::NotFound {
+                    key: sidx.clone(),
+                    nearest: index.last().map(day_key),
+                })?;
+            let raw = store.report_raw(province, &key);
+            report_response(req, &index, &key, raw, "day", render_day).await
+        }
+        None => Err(AppError::BadRequest("missing day number".into())),
+    }
+}
+
+fn css_view(request: Request, _ctx: RouteContext<()>) -> Result<Response> {
+    let resp = conditional_response(
+        &request,
+        &etag(&[CSS.as_bytes()]),
+        CACHE_STATIC,
+        "text/css",
+        || CSS.to_string(),
+    )?;
+    Ok(resp)
+}
+
+fn redirect(location: &str) -> AppResult<Response> {
+    let mut resp = Response::empty()?.with_status(302);
+    resp.headers_mut().set("location", location)?;
+    Ok(resp)
+}
+
+fn craft_response(
+    content_type: &str,
+    body: &str,
+    etag: &str,
+    cache_control: &str,
+) -> AppResult<Response> {
     let data = body.as_bytes().to_vec();
     let mut resp = Response::from_body(ResponseBody::Body(data))?;
     let headers = resp.headers_mut();
     headers.set("content-type", content_type)?;
-    //tell browser to cache for few seconds
-    headers.set("cache-control", "max-age=180")?;
+    headers.set("etag", etag)?;
+    headers.set("cache-control", cache_control)?;
     Ok(resp)
 }
 
-async fn get_kvval(kv: &kv::KvStore, key: &str) -> Result<String> {
-    let value = kv.get(key).cache_ttl(TTL_CACHE).text().await?;
-    value.ok_or_else(|| {
-        let mut msg = String::from("No such value in keystore -> ");
-        msg.push_str(key);
-        msg.into()
-    })
-}
-
-async fn chart_nonicu_view(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let kv = ctx.kv("VAXKV")?;
-    let dose0 = get_kvval(&kv, "nonicu_dose0").await?;
-    let dose2 = get_kvval(&kv, "nonicu_dose2").await?;
-    let dose_lt2 = get_kvval(&kv, "nonicu_dose_lt2").await?;
-    let title =
-        "<h3>COVID-19 hospitalizations (not in ICU) by vaccination status per 100,000 people in Ontario, Canada.</h3>";
-    chart_response(&kv, title, &dose0, &dose2, &dose_lt2).await
-}
-
-async fn chart_icu_view(_req: Request, ctx: RouteContext<()>) -> Result<Response> {
-    let kv = ctx.kv("VAXKV")?;
-    let dose0 = get_kvval(&kv, "icu_dose0").await?;
-    let dose2 = get_kvval(&kv, "icu_dose2").await?;
-    let dose_lt2 = get_kvval(&kv, "icu_dose_lt2").await?;
-    let title =
-        "<h3>COVID-19 hospitalization in ICU by vaccination status per 100,000 people in Ontario, Canada.</h3>";
-    chart_response(&kv, title, &dose0, &dose2, &dose_lt2).await
+//?from=YYYY-MM-DD&to=YYYY-MM-DD narrow the chart to a range of days, either may be left out
+//?interpolate=1 draws the days without a report as the line between the days around them
+//the chart is the slug of an outcome, every outcome in the series registry has a page
+async fn chart_view(req: &Request, ctx: &RouteContext<()>) -> AppResult<Response> {
+    let slug = ctx.param("chart").map_or("", |s| s.as_str());
+    let outcome = Outcome::by_slug(slug).ok_or_else(|| AppError::NotFound {
+        key: slug.to_string(),
+        nearest: None,
+    })?;
+    let lang = request_lang(req);
+    let url = req.url()?;
+    let query_date = |name: &str| -> AppResult<Option<NaiveDate>> {
+        match url.query_pairs().find(|(k, _)| k == name) {
+            Some((_, v)) if v.is_empty() => Ok(None),
+            Some((_, v)) => NaiveDate::parse_from_str(&v, "%Y-%m-%d")
+                .map(Some)
+                .map_err(|_| AppError::BadRequest(format!("`{}` is not a date", v))),
+            None => Ok(None),
+        }
+    };
+    let (first, last) = (query_date("from")?, query_date("to")?);
+    let interpolate = url
+        .query_pairs()
+        .any(|(k, v)| k == "interpolate" && v == "1");
+    let province = request_province(ctx)?;
+    let store = Store::open(ctx, province).await?;
+    let index = store.index(province).await?;
+    let page = format!("{} {:?} {:?} {}", outcome.path(), first, last, interpolate);
+    let etag = index_etag(&index, lang, &page);
+    if etag_matches(req.headers().get("if-none-match")?.as_deref(), &etag) {
+        return not_modified(&etag, CACHE_LATEST);
+    }
+    let mut chart = store.chart(province, outcome, first, last).await?;
+    if interpolate {
+        chart = chart.interpolated(outcome);
+    }
+    let body = render_chart_str(outcome, province, lang, &chart)?;
+    craft_response("text/html", &body, &etag, CACHE_LATEST)
 }
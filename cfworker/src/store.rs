@@ -0,0 +1,261 @@
+use crate::{AppError, AppResult};
+use chrono::NaiveDate;
+use ontariopublic::{
+    decode, index_from_rows, key_date, ChartRow, ChartSeries, DateRow, Index, Outcome, Phu,
+    Province, ReportRow, SkippedRow, Snapshot, UpdatedRow, CHART_QUERY, INDEX_QUERY, REPORT_QUERY,
+    SKIPPED_QUERY, SNAPSHOT_POINTER, UPDATED_QUERY,
+};
+use serde::de::DeserializeOwned;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use worker::d1::D1Database;
+use worker::kv::KvStore;
+use worker::wasm_bindgen::JsValue;
+use worker::{Date, RouteContext};
+
+//how long in seconds to cache key value store get results for
+const TTL_CACHE: u64 = 60;
+
+thread_local! {
+    //by province code, the pointer last read and when in milliseconds, read again after TTL_CACHE
+    static POINTERS: RefCell<HashMap<&'static str, (Option<String>, u64)>> =
+        RefCell::new(HashMap::new());
+    //the snapshots this isolate loaded by province code, an isolate interleaves requests at
+    //their awaits so no borrow is held across one and a late load never replaces a newer one
+    static SNAPSHOTS: RefCell<HashMap<&'static str, Rc<Snapshot>>> = RefCell::new(HashMap::new());
+}
+
+//where the provincial reports come from, the database when wrangler.toml binds VAXDB,
+//the snapshot when import wrote one with --snapshot otherwise the separate values in VAXKV
+pub enum Store {
+    Kv(KvStore),
+    D1(D1Database),
+    Snapshot(Rc<Snapshot>),
+}
+
+impl Store {
+    pub async fn open(ctx: &RouteContext<()>, province: &Province) -> AppResult<Store> {
+        if let Ok(db) = ctx.d1("VAXDB") {
+            return Ok(Store::D1(db));
+        }
+        let kv = ctx.kv("VAXKV")?;
+        match snapshot_pointer(&kv, province).await? {
+            Some(version) => Ok(Store::Snapshot(
+                load_snapshot(&kv, province, &version).await?,
+            )),
+            None => Ok(Store::Kv(kv)),
+        }
+    }
+
+    pub async fn index(&self, province: &Province) -> AppResult<Index> {
+        let index = match self {
+            Store::Kv(kv) => get_kvraw(kv, &province.key("index"))
+                .await?
+                .map(|b| decode(&b))
+                .transpose()?,
+            Store::D1(db) => {
+                let code = [JsValue::from(province.code)];
+                let dates: Vec<DateRow> = db
+                    .prepare(INDEX_QUERY)
+                    .bind(&code)?
+                    .all()
+                    .await?
+                    .results()?;
+                let skipped: Vec<SkippedRow> = db
+                    .prepare(SKIPPED_QUERY)
+                    .bind(&code)?
+                    .all()
+                    .await?
+                    .results()?;
+                let updated: Option<UpdatedRow> =
+                    db.prepare(UPDATED_QUERY).bind(&code)?.first(None).await?;
+                match updated {
+                    Some(updated) => index_from_rows(province, &dates, &skipped, &updated)?,
+                    None => None,
+                }
+            }
+            Store::Snapshot(snapshot) => Some(snapshot.index.clone()),
+        };
+        //a province import never ran for has no pages, ontario's missing index is an outage
+        index.ok_or_else(|| {
+            if province.is_ontario() {
+                AppError::Storage("could not fetch index".into())
+            } else {
+                AppError::NotFound {
+                    key: province.code.to_string(),
+                    nearest: None,
+                }
+            }
+        })
+    }
+
+    pub async fn report<R: DeserializeOwned>(
+        &self,
+        province: &Province,
+        key: &str,
+    ) -> AppResult<R> {
+        let raw = self.report_raw(province, key).await?;
+        Ok(decode(&raw)?)
+    }
+
+    //the stored report, json or the compact encoding, it doubles as the content the etag is computed from
+    pub async fn report_raw(&self, province: &Province, key: &str) -> AppResult<Vec<u8>> {
+        let opt = match self {
+            Store::Kv(kv) => get_kvraw(kv, &province.key(key)).await?,
+            Store::D1(db) => {
+                let date = key_date(key)
+                    .ok_or_else(|| AppError::BadRequest(format!("`{}` is not a date", key)))?;
+                let row: Option<ReportRow> = db
+                    .prepare(REPORT_QUERY)
+                    .bind(&[
+                        JsValue::from(province.code),
+                        JsValue::from(date.format("%Y-%m-%d").to_string()),
+                    ])?
+                    .first(None)
+                    .await?;
+                row.map(|r| r.report.into_bytes())
+            }
+            Store::Snapshot(snapshot) => key_date(key)
+                .and_then(|day| snapshot.report_raw(day))
+                .map(<[u8]>::to_vec),
+        };
+        //views check the key against the index first so a missing value is a storage problem
+        opt.ok_or_else(|| AppError::Storage(format!("could not fetch report `{}`", key)))
+    }
+
+    //the regions are only in VAXKV whichever store holds the provincial reports
+    pub fn regions(ctx: &RouteContext<()>) -> AppResult<Store> {
+        Ok(Store::Kv(ctx.kv("VAXKV")?))
+    }
+
+    //a unit import had no data for has no index, for the reader that is a missing page
+    pub async fn region_index(&self, phu: &Phu) -> AppResult<Index> {
+        let bytes = get_kvraw(self.region_kv()?, &phu.key("index")).await?;
+        match bytes {
+            Some(bytes) => Ok(decode(&bytes)?),
+            None => Err(AppError::NotFound {
+                key: phu.slug.to_string(),
+                nearest: None,
+            }),
+        }
+    }
+
+    //the stored region report, like report_raw it is also what the etag is computed from
+    pub async fn region_report_raw(&self, phu: &Phu, key: &str) -> AppResult<Vec<u8>> {
+        let opt = get_kvraw(self.region_kv()?, &phu.key(key)).await?;
+        opt.ok_or_else(|| AppError::Storage(format!("could not fetch report `{}`", key)))
+    }
+
+    fn region_kv(&self) -> AppResult<&KvStore> {
+        match self {
+            Store::Kv(kv) => Ok(kv),
+            _ => Err(AppError::Storage(
+                "regions are only in the key value store".into(),
+            )),
+        }
+    }
+
+    //a chart of outcome over the days from first to last inclusive
+    pub async fn chart(
+        &self,
+        province: &Province,
+        outcome: Outcome,
+        first: Option<NaiveDate>,
+        last: Option<NaiveDate>,
+    ) -> AppResult<ChartSeries> {
+        match self {
+            Store::Kv(kv) => {
+                let mut series = ChartSeries {
+                    labels: get_kvval(kv, &province.key("labels")).await?,
+                    lines: vec![],
+                };
+                for s in outcome.series() {
+                    series
+                        .lines
+                        .push(get_kvval(kv, &province.key(s.key)).await?);
+                }
+                Ok(series.within(first, last))
+            }
+            Store::D1(db) => {
+                let bound = |d: Option<NaiveDate>, default: &str| {
+                    JsValue::from(
+                        d.map_or(default.to_string(), |d| d.format("%Y-%m-%d").to_string()),
+                    )
+                };
+                let rows: Vec<ChartRow> = db
+                    .prepare(CHART_QUERY)
+                    .bind(&[
+                        JsValue::from(province.code),
+                        JsValue::from(outcome.name()),
+                        bound(first, "0000-01-01"),
+                        bound(last, "9999-12-31"),
+                    ])?
+                    .all()
+                    .await?
+                    .results()?;
+                Ok(ChartSeries::from_rows(outcome, rows))
+            }
+            Store::Snapshot(snapshot) => snapshot.chart(outcome, first, last).ok_or_else(|| {
+                AppError::Storage(format!("no chart `{}` in snapshot", outcome.name()))
+            }),
+        }
+    }
+}
+
+//the version the pointer names, from memory while the last read is recent enough
+async fn snapshot_pointer(kv: &KvStore, province: &Province) -> AppResult<Option<String>> {
+    let now = Date::now().as_millis();
+    let read = POINTERS.with(|p| p.borrow().get(province.code).cloned());
+    let fresh = read.filter(|(_, at)| now.saturating_sub(*at) < TTL_CACHE * 1000);
+    if let Some((version, _)) = fresh {
+        return Ok(version);
+    }
+    //an import without --snapshot leaves the pointer empty
+    let version = kv
+        .get(&province.key(SNAPSHOT_POINTER))
+        .cache_ttl(TTL_CACHE)
+        .text()
+        .await?
+        .filter(|v| !v.is_empty());
+    POINTERS.with(|p| p.borrow_mut().insert(province.code, (version.clone(), now)));
+    Ok(version)
+}
+
+//the snapshot of version, from memory unless an import since the last request moved the pointer
+async fn load_snapshot(
+    kv: &KvStore,
+    province: &Province,
+    version: &str,
+) -> AppResult<Rc<Snapshot>> {
+    let loaded = SNAPSHOTS.with(|s| s.borrow().get(province.code).cloned());
+    if let Some(snapshot) = loaded.filter(|s| s.version() == version) {
+        return Ok(snapshot);
+    }
+    let key = Snapshot::key(province, version);
+    let bytes = kv
+        .get(&key)
+        .bytes()
+        .await?
+        .ok_or_else(|| AppError::Storage(format!("no such value in keystore `{}`", key)))?;
+    let snapshot = Rc::new(Snapshot::from_bytes(&bytes)?);
+    //another request may have read a newer pointer while this one was fetching
+    let moved = POINTERS.with(|p| {
+        p.borrow()
+            .get(province.code)
+            .map_or(false, |(v, _)| v.as_deref() != Some(version))
+    });
+    if !moved {
+        SNAPSHOTS.with(|s| s.borrow_mut().insert(province.code, snapshot.clone()));
+    }
+    Ok(snapshot)
+}
+
+async fn get_kvraw(kv: &KvStore, key: &str) -> AppResult<Option<Vec<u8>>> {
+    Ok(kv.get(key).cache_ttl(TTL_CACHE).bytes().await?)
+}
+
+async fn get_kvval<T: DeserializeOwned>(kv: &KvStore, key: &str) -> AppResult<T> {
+    let value = kv.get(key).cache_ttl(TTL_CACHE).json().await?;
+    value.ok_or_else(|| AppError::Storage(format!("no such value in keystore `{}`", key)))
+}
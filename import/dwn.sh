@@ -3,4 +3,12 @@
 # get data from  https://data.ontario.ca/dataset/covid-19-vaccine-data-in-ontario
 curl -o cases_by_vac_status.json https://data.ontario.ca/datastore/dump/eed63cf2-83dd-4598-b337-b288c0a89a16?format=json
 curl -o hosp_by_vac_status.json https://data.ontario.ca/datastore/dump/274b819c-5d69-4539-a4db-f2950794138c?format=json
-
+curl -o vaccines_by_age.json https://data.ontario.ca/datastore/dump/775ca815-5028-4e9b-9dd4-6975ff1be021?format=json
+# get data from https://data.ontario.ca/dataset/status-of-covid-19-cases-in-ontario
+curl -o covidtesting.json https://data.ontario.ca/datastore/dump/ed270bb8-340b-41f9-a7c6-e8ef587e6d11?format=json
+# get data from https://data.ontario.ca/dataset/status-of-covid-19-cases-in-ontario-by-public-health-unit-phu
+curl -o cases_by_status_and_phu.json https://data.ontario.ca/datastore/dump/d1bfe1ad-6575-4352-8302-09ca81f7ddfc?format=json
+curl -o vaccines_by_age_phu.json https://data.ontario.ca/datastore/dump/2a362139-b782-43b1-b3cb-078a2ef19524?format=json
+# cases by age and vaccination status from https://data.ontario.ca/dataset/covid-19-vaccine-data-in-ontario
+# hosp_by_age_vac_status.json is not in the datastore, export it by hand when age standardised hospitalizations are wanted
+curl -o cases_by_age_vac_status.json https://data.ontario.ca/datastore/dump/c08620e0-a055-4d35-8cec-875a459642c3?format=json
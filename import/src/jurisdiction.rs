@@ -0,0 +1,65 @@
+use crate::Entry;
+use anyhow::{Context, Result};
+use chrono::NaiveDate;
+use ontariopublic::{
+    reports_from_status_rows, DayReport, Encoding, GapCause, Province, StatusCsvRow,
+};
+use std::fs::File;
+use std::io::BufReader;
+
+//a source of day reports for one province, whatever the province publishes ends up as DayReport
+//so the index, the charts and the worker work the same for every one of them
+pub trait Jurisdiction {
+    fn province(&self) -> &'static Province;
+
+    //every day that could be read and passed validation, days that did not are logged and skipped
+    fn reports(&self) -> Result<Reports>;
+
+    //anything else the province publishes besides its day reports and charts,
+    //stored in encoding like the day reports
+    fn extra_entries(&self, _encoding: Encoding) -> Result<Vec<Entry>> {
+        Ok(vec![])
+    }
+}
+
+//what a jurisdiction read, the days it left out go into the index so the gaps have a cause
+#[derive(Default)]
+pub struct Reports {
+    pub days: Vec<DayReport>,
+    pub skipped: Vec<(NaiveDate, GapCause)>,
+}
+
+//a province publishing one row per day and vaccination status, see StatusCsvRow for the columns
+pub struct StatusCsv<'a> {
+    pub province: &'static Province,
+    pub fname: &'a str,
+}
+
+impl Jurisdiction for StatusCsv<'_> {
+    fn province(&self) -> &'static Province {
+        self.province
+    }
+
+    fn reports(&self) -> Result<Reports> {
+        let f = File::open(self.fname)
+            .with_context(|| format!("Failed to read from {}", self.fname))?;
+        let mut reader = csv::Reader::from_reader(BufReader::new(f));
+        let mut rows = vec![];
+        for record in reader.deserialize() {
+            let row: StatusCsvRow = record
+                .with_context(|| format!("Reading {} into StatusCsvRow failed", self.fname))?;
+            rows.push(row);
+        }
+        let mut reports = Reports::default();
+        for (date, r) in reports_from_status_rows(&rows) {
+            match r {
+                Ok(report) => reports.days.push(report),
+                Err(err) => {
+                    println!("Skipping invalid day of {} due to {:?}", self.fname, err);
+                    reports.skipped.push((date, GapCause::FailedValidation));
+                }
+            }
+        }
+        Ok(reports)
+    }
+}
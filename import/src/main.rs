@@ -1,31 +1,74 @@
-use anyhow::{anyhow, Context, Result};
+use anyhow::{Context, Result};
 use clap::{Arg, Command};
+use jurisdiction::{Jurisdiction, Reports, StatusCsv};
+use ontario::{Ontario, CSV_FNAME, FNAME};
 use ontariopublic::{
-    CasesByVacStatus, CasesByVacStatusRoot, CsvCase, CsvCasesRoot, DayReport,
-    HospitalizationByVacStatusRoot, Index,
+    ChartSeries, DatastoreClient, Encoding, Index, Outcome, Province, Snapshot, PROVINCES, SERIES,
+    SNAPSHOT_POINTER,
 };
-use rust_decimal::prelude::*;
 use serde::Serialize;
-use std::collections::HashMap;
-use std::fs::File;
-use std::io::BufReader;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+
+mod jurisdiction;
+mod ontario;
+mod sqlite;
+mod webhook;
 
-const FNAME: &str = "cases_by_vac_status.json";
-const CSV_FNAME: &str = "cases_by_vac_status.csv";
-const HFNAME: &str = "hosp_by_vac_status.json";
 const OUTFNAME: &str = "bulk.json";
 
 #[derive(Serialize, Debug)]
-struct Entry {
-    key: String,
-    value: String,
+pub struct Entry {
+    pub key: String,
+    pub value: String,
+    //wrangler kv:bulk decodes the value before storing it, set for the compact encoding
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub base64: bool,
+}
+
+impl Entry {
+    pub fn json<T: Serialize>(key: String, value: &T) -> Result<Entry> {
+        Ok(Entry {
+            key,
+            value: serde_json::to_string(value)?,
+            base64: false,
+        })
+    }
+
+    //the day reports, the region reports and their indexes, what every page view reads
+    pub fn encoded<T: Serialize>(key: String, value: &T, encoding: Encoding) -> Result<Entry> {
+        match encoding {
+            Encoding::Json => Entry::json(key, value),
+            Encoding::Cbor => Ok(Entry {
+                key,
+                value: base64::encode(encoding.encode(value)?),
+                base64: true,
+            }),
+        }
+    }
+}
+
+//where import writes to, the key value bulk file for wrangler or a database for sql
+#[derive(Debug, PartialEq)]
+enum Output<'a> {
+    Bulk(&'a str),
+    Sqlite(&'a str),
+}
+
+impl<'a> Output<'a> {
+    fn parse(target: &'a str) -> Output<'a> {
+        match target.strip_prefix("sqlite:") {
+            Some(path) => Output::Sqlite(path),
+            None => Output::Bulk(target.strip_prefix("bulk:").unwrap_or(target)),
+        }
+    }
 }
 
 fn main() -> Result<()> {
     let matches = Command::new("VaxImport")
         .version("0.1")
         .author("Jakub Labath. <jakub@labath.ca>")
-        .about("Serializes Ontario Health data into a format we can use")
+        .about("Serializes provincial health data into a format we can use")
         .arg(
             Arg::new("cases file")
                 .long("cases")
@@ -34,189 +77,203 @@ fn main() -> Result<()> {
                 .possible_values([FNAME, CSV_FNAME])
                 .default_value(CSV_FNAME),
         )
+        .arg(
+            Arg::new("province")
+                .long("province")
+                .help("Code of the province the sources are for, anything but on reads --status-csv")
+                .takes_value(true)
+                .default_value("on"),
+        )
+        .arg(
+            Arg::new("status csv")
+                .long("status-csv")
+                .help("CSV with one row per day and vaccination status: date,status,cases,population,hospitalnonicu,icu")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .help("Where to write, a bulk file path or sqlite:path.db, may be repeated")
+                .takes_value(true)
+                .multiple_occurrences(true)
+                .default_value(OUTFNAME),
+        )
+        .arg(
+            Arg::new("encoding")
+                .long("encoding")
+                .help("How day reports and the index are stored in the bulk file, cbor is smaller and faster to read")
+                .takes_value(true)
+                .possible_values(["json", "cbor"])
+                .default_value("json"),
+        )
+        .arg(
+            Arg::new("snapshot")
+                .long("snapshot")
+                .help("Also write every report and chart of the province as one compressed value the worker keeps in memory"),
+        )
+        .arg(
+            Arg::new("datastore")
+                .long("datastore")
+                .help("Read the json sources from the datastore_search api of this site e.g. https://data.ontario.ca")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::new("webhook")
+                .long("webhook")
+                .help("URL to POST the added and revised days to, may be repeated")
+                .takes_value(true)
+                .multiple_occurrences(true),
+        )
+        .arg(
+            Arg::new("webhook secret")
+                .long("webhook-secret")
+                .help("Key used to sign webhook payloads with HMAC-SHA256")
+                .takes_value(true),
+        )
         .get_matches();
 
-    let cases_by_vac = cases_by_vac_status(matches.value_of("cases file"))?;
-    let f_hosp = File::open(HFNAME).with_context(|| format!("Failed to read from {}", HFNAME))?;
-    let br_hosp = BufReader::new(f_hosp);
-    let hosp_by_vac: HospitalizationByVacStatusRoot = serde_json::from_reader(br_hosp)
-        .with_context(|| format!("Failed to deserialize {}", HFNAME))?;
-    //validate the root of hospitalizations by status
-    hosp_by_vac.validate()?;
-    let mut hosp_map = HashMap::new();
-    let mut reports = vec![];
+    let codes: Vec<&str> = PROVINCES.iter().map(|p| p.code).collect();
+    let requested = matches
+        .value_of("province")
+        .and_then(Province::by_code)
+        .ok_or_else(|| anyhow::anyhow!("Invalid value for province, one of {:?}", codes))?;
+    let jurisdiction: Box<dyn Jurisdiction> = if requested.is_ontario() {
+        Box::new(Ontario {
+            cases_file: matches.value_of("cases file"),
+            client: matches.value_of("datastore").map(DatastoreClient::new),
+        })
+    } else {
+        let fname = matches
+            .value_of("status csv")
+            .context("Provinces other than Ontario need --status-csv")?;
+        Box::new(StatusCsv {
+            province: requested,
+            fname,
+        })
+    };
+    let province = jurisdiction.province();
+    let encoding = matches
+        .value_of("encoding")
+        .and_then(Encoding::by_name)
+        .unwrap_or(Encoding::Json);
+    let Reports {
+        days: mut reports,
+        skipped,
+    } = jurisdiction.reports()?;
     let mut entries = vec![];
-    let mut keys: Vec<String> = Vec::new();
-    //variables for charts
+    let mut current = HashMap::new();
+    //variables for charts, a line per series
     let mut labels = vec![];
-    let mut cases_dose0 = vec![];
-    let mut cases_dose_lt2 = vec![];
-    let mut cases_dose2 = vec![];
-    let mut hosp_dose0 = vec![];
-    let mut hosp_dose2 = vec![];
-    let mut hosp_dose_lt2 = vec![];
-    let mut icu_dose0 = vec![];
-    let mut icu_dose2 = vec![];
-    let mut icu_dose_lt2 = vec![];
-    //put the hospitalizations in a map
-    for r in hosp_by_vac {
-        match r {
-            Ok(h) => {
-                hosp_map.insert(h.date, h);
-            }
-            Err(err) => {
-                println!("Error when iterating hosp_by_vac {:?} {}", err, err);
-            }
-        }
-    }
-    //iterate cases to to create dayreports use the map to add data
-    for r in cases_by_vac {
-        match r {
-            Ok(cases) => {
-                let check = cases.validate();
-                if check.is_err() {
-                    println!(
-                        "Skipping processing invalid cases {:?} due to {:?}",
-                        &cases, check
-                    );
-                    continue;
-                }
-                match hosp_map.remove(&cases.date) {
-                    Some(hosps) => {
-                        let report = DayReport::from(cases, hosps);
-                        if let Err(e) = report.validate() {
-                            println!("Skipping  invalid report {} due to {:?}", report.key(), e);
-                            continue;
-                        }
-                        reports.push(report);
-                    }
-                    None => {
-                        println!("Error did not find hospitalization for {}", cases.date);
-                    }
-                }
-            }
-            Err(err) => {
-                println!("Error when iterating cases_by_vac {:?} {}", err, err);
-            }
-        }
-    }
+    let mut lines: Vec<Vec<Option<f64>>> = vec![vec![]; SERIES.len()];
     //sort reports
     reports.sort_unstable_by_key(|k| k.key());
-    for r in reports {
-        let entry = Entry {
-            key: r.key(),
-            value: serde_json::to_string(&r)?,
-        };
-        keys.push(r.key());
-        entries.push(entry);
+    for r in &reports {
+        //webhooks compare the json whatever the encoding
+        current.insert(r.key(), serde_json::to_string(r)?);
+        entries.push(Entry::encoded(province.key(&r.key()), r, encoding)?);
         //charts
         labels.push(r.cases.date.format("%Y-%m-%d").to_string());
-        cases_dose0.push(chart_float_opt(r.cases.cases_unvac_rate_per100k));
-        cases_dose_lt2.push(chart_float_opt(r.cases.cases_notfull_vac_rate_per100k));
-        cases_dose2.push(chart_float(r.cases.cases_full_vac_rate_per100k));
-        hosp_dose0.push(chart_float_opt(r.nonicu_unvac_rate_per100k()));
-        hosp_dose_lt2.push(chart_float_opt(r.nonicu_notfull_vac_rate_per100k()));
-        hosp_dose2.push(chart_float(r.nonicu_full_vac_rate_per100k()));
-        icu_dose0.push(chart_float_opt(r.icu_unvac_rate_per100k()));
-        icu_dose2.push(chart_float(r.icu_full_vac_rate_per100k()));
-        icu_dose_lt2.push(chart_float_opt(r.icu_notfull_vac_rate_per100k()));
+        for (s, line) in SERIES.iter().zip(lines.iter_mut()) {
+            line.push(s.value(r));
+        }
     }
     //now add the index
-    let index = Index::from(keys.as_slice());
-    entries.push(Entry {
-        key: "index".into(),
-        value: serde_json::to_string(&index)?,
-    });
+    let index = Index::new(reports.iter().map(|r| r.cases.date))
+        .with_province(province)
+        .with_skipped(skipped);
+    report_gaps(&index);
+    entries.push(Entry::encoded(province.key("index"), &index, encoding)?);
+    entries.extend(jurisdiction.extra_entries(encoding)?);
     //now add the chart entries
+    entries.push(Entry::json(province.key("labels"), &labels)?);
+    for (s, line) in SERIES.iter().zip(&lines) {
+        entries.push(Entry::json(province.key(s.key), line)?);
+    }
+    //written on every run, empty without --snapshot so the worker stops serving the snapshot
+    //of an earlier import and reads the values above
+    let mut pointer = String::new();
+    if matches.is_present("snapshot") {
+        let mut charts = BTreeMap::new();
+        for outcome in Outcome::ALL {
+            let chart = ChartSeries::from_lines(outcome, &labels, &lines);
+            charts.insert(outcome.name().to_string(), chart);
+        }
+        let snapshot = Snapshot::new(index.clone(), reports.clone(), charts)?;
+        pointer = snapshot.version();
+        entries.push(Entry {
+            key: Snapshot::key(province, &pointer),
+            value: base64::encode(snapshot.to_bytes()?),
+            base64: true,
+        });
+    }
+    //plain text, the worker reads it as is
     entries.push(Entry {
-        key: "labels".into(),
-        value: serde_json::to_string(&labels)?,
-    });
-    entries.push(Entry {
-        key: "cases_dose0".into(),
-        value: serde_json::to_string(&cases_dose0)?,
-    });
-    entries.push(Entry {
-        key: "cases_dose2".into(),
-        value: serde_json::to_string(&cases_dose2)?,
-    });
-    entries.push(Entry {
-        key: "cases_dose_lt2".into(),
-        value: serde_json::to_string(&cases_dose_lt2)?,
-    });
-    entries.push(Entry {
-        key: "nonicu_dose0".into(),
-        value: serde_json::to_string(&hosp_dose0)?,
-    });
-    entries.push(Entry {
-        key: "nonicu_dose2".into(),
-        value: serde_json::to_string(&hosp_dose2)?,
-    });
-    entries.push(Entry {
-        key: "nonicu_dose_lt2".into(),
-        value: serde_json::to_string(&hosp_dose_lt2)?,
-    });
-    entries.push(Entry {
-        key: "icu_dose0".into(),
-        value: serde_json::to_string(&icu_dose0)?,
-    });
-    entries.push(Entry {
-        key: "icu_dose2".into(),
-        value: serde_json::to_string(&icu_dose2)?,
-    });
-    entries.push(Entry {
-        key: "icu_dose_lt2".into(),
-        value: serde_json::to_string(&icu_dose_lt2)?,
+        key: province.key(SNAPSHOT_POINTER),
+        value: pointer,
+        base64: false,
     });
 
-    let fout = File::create(OUTFNAME)
-        .with_context(|| format!("Failed to open {} for writing", OUTFNAME))?;
-    let _ = serde_json::to_writer(fout, &entries)?;
-    Ok(())
-}
-
-fn chart_float(n: Decimal) -> f64 {
-    n.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
-        .to_f64()
-        .unwrap_or(0.0)
-}
-
-fn chart_float_opt(inp: Option<Decimal>) -> Option<f64> {
-    inp.map(|n| {
-        n.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
-            .to_f64()
-            .unwrap_or(0.0)
-    })
-}
-
-fn cases_by_vac_status(
-    fname: Option<&str>,
-) -> Result<Box<dyn Iterator<Item = ontariopublic::Result<CasesByVacStatus>>>> {
-    match fname {
-        Some(FNAME) => {
-            let f = File::open(FNAME).with_context(|| format!("Failed to read from {}", FNAME))?;
-            let br = BufReader::new(f);
-            let cases_by_vac: CasesByVacStatusRoot = serde_json::from_reader(br)
-                .with_context(|| format!("Failed to deserialize {}", FNAME))?;
-            //validate the root object of cases
-            cases_by_vac.validate()?;
-            Ok(Box::new(cases_by_vac.into_iter()))
-        }
-        Some(CSV_FNAME) => {
-            let mut cases = vec![];
-            let fname = CSV_FNAME;
-            let f = File::open(fname).with_context(|| format!("Failed to read from {}", fname))?;
-            let br = BufReader::new(f);
-            let mut reader = csv::Reader::from_reader(br);
+    let outputs: Vec<Output> = matches
+        .values_of("output")
+        .into_iter()
+        .flatten()
+        .map(Output::parse)
+        .collect();
+    //read what was published last time before it gets overwritten
+    let webhooks: Vec<&str> = matches.values_of("webhook").into_iter().flatten().collect();
+    let previous = if webhooks.is_empty() {
+        HashMap::new()
+    } else {
+        let published = outputs
+            .iter()
+            .find_map(|o| match o {
+                Output::Bulk(fname) => Some(*fname),
+                Output::Sqlite(_) => None,
+            })
+            .context("Webhooks compare against the previous bulk file, add a bulk --output")?;
+        webhook::previous_reports(published, province)?
+    };
 
-            for record in reader.deserialize() {
-                let record: CsvCase = record.context("Reading data into Case struct failed")?;
-                //println!("It is a {:?}.", record);
-                cases.push(record);
+    //bulk files replace the previous ones only once the webhooks were told, the previous file
+    //is what the next run diffs against so a failed notification is sent again
+    let mut written = vec![];
+    for output in &outputs {
+        match output {
+            Output::Bulk(fname) => {
+                let tmp = format!("{}.tmp", fname);
+                let fout = File::create(&tmp)
+                    .with_context(|| format!("Failed to open {} for writing", tmp))?;
+                serde_json::to_writer(fout, &entries)?;
+                written.push((tmp, *fname));
             }
+            Output::Sqlite(path) => sqlite::write(path, province, &reports, &index)?,
+        }
+    }
 
-            Ok(Box::new(CsvCasesRoot(cases).into_iter()))
+    if !webhooks.is_empty() {
+        let payload =
+            webhook::Payload::diff(province, &previous, &current, index.updated.to_rfc3339());
+        if payload.is_empty() {
+            println!("No days added or revised, not calling webhooks");
+        } else {
+            webhook::notify(&webhooks, matches.value_of("webhook secret"), &payload)?;
         }
-        _ => Err(anyhow!("Invalid value for cases param {:?}", fname)),
+    }
+    for (tmp, fname) in written {
+        fs::rename(&tmp, fname).with_context(|| format!("Failed to move {} to {}", tmp, fname))?;
+    }
+    Ok(())
+}
+
+//the days between the first and the last report without one, the calendar shows them too
+fn report_gaps(index: &Index) {
+    let gaps = index.gaps();
+    if gaps.is_empty() {
+        return;
+    }
+    let days: i64 = gaps.iter().map(|g| g.days()).sum();
+    println!("{} days without a report in {} gaps", days, gaps.len());
+    for g in gaps {
+        println!("  {} to {} {}", g.first, g.last, g.cause.name());
     }
 }
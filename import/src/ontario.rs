@@ -0,0 +1,339 @@
+//ontario's datasets from data.ontario.ca, the first jurisdiction the site published
+use crate::jurisdiction::{Jurisdiction, Reports};
+use crate::Entry;
+use anyhow::{anyhow, Context, Result};
+use chrono::NaiveDate;
+use ontariopublic::{
+    AgeRates, AgeReport, CasesByVacStatus, CasesByVacStatusRoot, CkanCasesByAge, CkanCasesByPhu,
+    CkanCovidTesting, CkanDump, CkanHospsByAge, CkanVaccinesByAge, CkanVaccinesByAgePhu, CsvCase,
+    CsvCasesRoot, DatastoreClient, DayReport, DosesByAge, Encoding, GapCause,
+    HospitalizationByVacStatusRoot, Index, Occupancy, Phu, PhuCases, Province, RegionReport,
+    ONTARIO,
+};
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+
+pub const FNAME: &str = "cases_by_vac_status.json";
+pub const CSV_FNAME: &str = "cases_by_vac_status.csv";
+const HFNAME: &str = "hosp_by_vac_status.json";
+const VFNAME: &str = "vaccines_by_age.json";
+const OFNAME: &str = "covidtesting.json";
+const PHU_CASES_FNAME: &str = "cases_by_status_and_phu.json";
+const PHU_VFNAME: &str = "vaccines_by_age_phu.json";
+const AGE_CASES_FNAME: &str = "cases_by_age_vac_status.json";
+//exported by hand, the hospitalizations by age are not in the datastore
+const AGE_HOSP_FNAME: &str = "hosp_by_age_vac_status.json";
+//datastore resources behind the json files, see dwn.sh
+const CASES_RESOURCE: &str = "eed63cf2-83dd-4598-b337-b288c0a89a16";
+const HOSP_RESOURCE: &str = "274b819c-5d69-4539-a4db-f2950794138c";
+const VACCINES_RESOURCE: &str = "775ca815-5028-4e9b-9dd4-6975ff1be021";
+const TESTING_RESOURCE: &str = "ed270bb8-340b-41f9-a7c6-e8ef587e6d11";
+const PHU_CASES_RESOURCE: &str = "d1bfe1ad-6575-4352-8302-09ca81f7ddfc";
+const PHU_VACCINES_RESOURCE: &str = "2a362139-b782-43b1-b3cb-078a2ef19524";
+const AGE_CASES_RESOURCE: &str = "c08620e0-a055-4d35-8cec-875a459642c3";
+
+pub struct Ontario<'a> {
+    //which of the cases files to read, FNAME or CSV_FNAME
+    pub cases_file: Option<&'a str>,
+    //reads the json sources through datastore_search instead of the downloaded files
+    pub client: Option<DatastoreClient>,
+}
+
+impl Jurisdiction for Ontario<'_> {
+    fn province(&self) -> &'static Province {
+        ONTARIO
+    }
+
+    fn reports(&self) -> Result<Reports> {
+        let client = self.client.as_ref();
+        let cases_by_vac = cases_by_vac_status(self.cases_file, client)?;
+        let hosp_by_vac = match client {
+            Some(client) => HospitalizationByVacStatusRoot(
+                client
+                    .search(HOSP_RESOURCE, &[])
+                    .context("Failed to fetch hospitalizations from the datastore")?,
+            ),
+            None => {
+                let f_hosp = File::open(HFNAME)
+                    .with_context(|| format!("Failed to read from {}", HFNAME))?;
+                let br_hosp = BufReader::new(f_hosp);
+                serde_json::from_reader(br_hosp)
+                    .with_context(|| format!("Failed to deserialize {}", HFNAME))?
+            }
+        };
+        //validate the root of hospitalizations by status
+        hosp_by_vac.validate()?;
+        let mut doses_map = doses_by_day(client)?;
+        let mut occupancy_map = occupancy_by_day(client)?;
+        let mut ages_map = ages_by_day(client)?;
+        let mut hosp_map = HashMap::new();
+        //days whose hospitalizations did not parse, their cases have nothing to join with
+        let mut hosp_failed = HashSet::new();
+        let mut reports = Reports::default();
+        //put the hospitalizations in a map
+        for r in hosp_by_vac {
+            match r {
+                Ok(h) => {
+                    hosp_map.insert(h.date, h);
+                }
+                Err(err) => {
+                    println!("Error when iterating hosp_by_vac {:?} {}", err, err);
+                    hosp_failed.extend(err.date());
+                }
+            }
+        }
+        //iterate cases to to create dayreports use the map to add data
+        for r in cases_by_vac {
+            match r {
+                Ok(cases) => {
+                    let check = cases.validate();
+                    if check.is_err() {
+                        println!(
+                            "Skipping processing invalid cases {:?} due to {:?}",
+                            &cases, check
+                        );
+                        reports
+                            .skipped
+                            .push((cases.date, GapCause::FailedValidation));
+                        continue;
+                    }
+                    match hosp_map.remove(&cases.date) {
+                        Some(hosps) => {
+                            let mut report = DayReport::from(cases, hosps);
+                            report.doses = doses_map.remove(&report.cases.date).unwrap_or_default();
+                            report.occupancy = occupancy_map.remove(&report.cases.date);
+                            report.ages = ages_map.remove(&report.cases.date);
+                            if let Err(e) = report.validate() {
+                                println!(
+                                    "Skipping  invalid report {} due to {:?}",
+                                    report.key(),
+                                    e
+                                );
+                                reports
+                                    .skipped
+                                    .push((report.cases.date, GapCause::FailedValidation));
+                                continue;
+                            }
+                            reports.days.push(report);
+                        }
+                        None if hosp_failed.contains(&cases.date) => {
+                            reports
+                                .skipped
+                                .push((cases.date, GapCause::FailedValidation));
+                        }
+                        None => {
+                            println!("Error did not find hospitalization for {}", cases.date);
+                            reports.skipped.push((cases.date, GapCause::UnmatchedJoin));
+                        }
+                    }
+                }
+                Err(err) => {
+                    println!("Error when iterating cases_by_vac {:?} {}", err, err);
+                    //a row without even a date can't be placed on the calendar
+                    if let Some(date) = err.date() {
+                        reports.skipped.push((date, GapCause::FailedValidation));
+                    }
+                }
+            }
+        }
+        //hospitalizations of days the cases do not have, unless the cases of the day were invalid
+        let failed: HashSet<NaiveDate> = reports.skipped.iter().map(|(date, _)| *date).collect();
+        reports.skipped.extend(
+            hosp_map
+                .into_keys()
+                .filter(|date| !failed.contains(date))
+                .map(|date| (date, GapCause::UnmatchedJoin)),
+        );
+        Ok(reports)
+    }
+
+    //the public health units get their own reports and index under r/<slug>/
+    fn extra_entries(&self, encoding: Encoding) -> Result<Vec<Entry>> {
+        let mut entries = vec![];
+        for (phu, reports) in region_reports(self.client.as_ref())?.into_values() {
+            let index = Index::new(reports.iter().map(|r| r.date));
+            for r in reports {
+                entries.push(Entry::encoded(phu.key(&r.key()), &r, encoding)?);
+            }
+            entries.push(Entry::encoded(phu.key("index"), &index, encoding)?);
+        }
+        Ok(entries)
+    }
+}
+
+fn cases_by_vac_status(
+    fname: Option<&str>,
+    client: Option<&DatastoreClient>,
+) -> Result<Box<dyn Iterator<Item = ontariopublic::Result<CasesByVacStatus>>>> {
+    match fname {
+        Some(FNAME) => {
+            let cases_by_vac = match client {
+                Some(client) => CasesByVacStatusRoot(
+                    client
+                        .search(CASES_RESOURCE, &[])
+                        .context("Failed to fetch cases from the datastore")?,
+                ),
+                None => {
+                    let f = File::open(FNAME)
+                        .with_context(|| format!("Failed to read from {}", FNAME))?;
+                    let br = BufReader::new(f);
+                    serde_json::from_reader(br)
+                        .with_context(|| format!("Failed to deserialize {}", FNAME))?
+                }
+            };
+            //validate the root object of cases
+            cases_by_vac.validate()?;
+            Ok(Box::new(cases_by_vac.into_iter()))
+        }
+        Some(CSV_FNAME) => {
+            let mut cases = vec![];
+            let fname = CSV_FNAME;
+            let f = File::open(fname).with_context(|| format!("Failed to read from {}", fname))?;
+            let br = BufReader::new(f);
+            let mut reader = csv::Reader::from_reader(br);
+
+            for record in reader.deserialize() {
+                let record: CsvCase = record.context("Reading data into Case struct failed")?;
+                //println!("It is a {:?}.", record);
+                cases.push(record);
+            }
+
+            Ok(Box::new(CsvCasesRoot(cases).into_iter()))
+        }
+        _ => Err(anyhow!("Invalid value for cases param {:?}", fname)),
+    }
+}
+
+//the coverage and occupancy datasets are optional, without a dump or the datastore the reports go without them
+fn optional_dump<T: DeserializeOwned>(
+    client: Option<&DatastoreClient>,
+    resource: &str,
+    fname: &str,
+) -> Result<Option<CkanDump<T>>> {
+    if let Some(client) = client {
+        let dump = client
+            .search(resource, &[])
+            .with_context(|| format!("Failed to fetch {} from the datastore", fname))?;
+        return Ok(Some(dump));
+    }
+    optional_file(fname)
+}
+
+//a dump read from disk, none when the file was not downloaded
+fn optional_file<T: DeserializeOwned>(fname: &str) -> Result<Option<CkanDump<T>>> {
+    let f = match File::open(fname) {
+        Ok(f) => f,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+            println!("No {} found, reports will not include it", fname);
+            return Ok(None);
+        }
+        Err(err) => return Err(err).with_context(|| format!("Failed to read from {}", fname)),
+    };
+    let dump = serde_json::from_reader(BufReader::new(f))
+        .with_context(|| format!("Failed to deserialize {}", fname))?;
+    Ok(Some(dump))
+}
+
+fn doses_by_day(client: Option<&DatastoreClient>) -> Result<HashMap<NaiveDate, Vec<DosesByAge>>> {
+    let mut map: HashMap<NaiveDate, Vec<DosesByAge>> = HashMap::new();
+    let dump: Option<CkanDump<CkanVaccinesByAge>> =
+        optional_dump(client, VACCINES_RESOURCE, VFNAME)?;
+    for r in dump.into_iter().flatten() {
+        match r.and_then(DosesByAge::try_from) {
+            Ok(d) => map.entry(d.date).or_default().push(d),
+            Err(err) => println!("Error when iterating doses by age {:?} {}", err, err),
+        }
+    }
+    Ok(map)
+}
+
+fn occupancy_by_day(client: Option<&DatastoreClient>) -> Result<HashMap<NaiveDate, Occupancy>> {
+    let mut map = HashMap::new();
+    let dump: Option<CkanDump<CkanCovidTesting>> = optional_dump(client, TESTING_RESOURCE, OFNAME)?;
+    for r in dump.into_iter().flatten() {
+        match r.and_then(Occupancy::try_from) {
+            Ok(o) => {
+                map.insert(o.date, o);
+            }
+            Err(err) => println!("Error when iterating occupancy {:?} {}", err, err),
+        }
+    }
+    Ok(map)
+}
+
+fn ages_by_day(client: Option<&DatastoreClient>) -> Result<HashMap<NaiveDate, AgeReport>> {
+    let mut map: HashMap<NaiveDate, AgeReport> = HashMap::new();
+    let dump: Option<CkanDump<CkanCasesByAge>> =
+        optional_dump(client, AGE_CASES_RESOURCE, AGE_CASES_FNAME)?;
+    for r in dump.into_iter().flatten() {
+        match r.and_then(AgeRates::try_from) {
+            Ok(a) => map.entry(a.date).or_default().cases.push(a),
+            Err(err) => println!("Error when iterating cases by age {:?} {}", err, err),
+        }
+    }
+    let dump: Option<CkanDump<CkanHospsByAge>> = optional_file(AGE_HOSP_FNAME)?;
+    for r in dump.into_iter().flatten() {
+        match r.and_then(CkanHospsByAge::into_rates) {
+            Ok([nonicu, icu]) => {
+                let report = map.entry(nonicu.date).or_default();
+                report.nonicu.push(nonicu);
+                report.icu.push(icu);
+            }
+            Err(err) => println!(
+                "Error when iterating hospitalizations by age {:?} {}",
+                err, err
+            ),
+        }
+    }
+    Ok(map)
+}
+
+//reports of every public health unit with data sorted by day, a day needs the unit's cases to have a report
+fn region_reports(
+    client: Option<&DatastoreClient>,
+) -> Result<BTreeMap<u32, (&'static Phu, Vec<RegionReport>)>> {
+    let mut days: BTreeMap<(u32, NaiveDate), RegionReport> = BTreeMap::new();
+    let dump: Option<CkanDump<CkanCasesByPhu>> =
+        optional_dump(client, PHU_CASES_RESOURCE, PHU_CASES_FNAME)?;
+    for r in dump.into_iter().flatten() {
+        match r.and_then(PhuCases::try_from) {
+            Ok(c) => {
+                let report = days
+                    .entry((c.phu, c.date))
+                    .or_insert_with(|| RegionReport::new(c.phu, c.date));
+                report.cases = Some(c);
+            }
+            Err(err) => println!("Error when iterating cases by phu {:?} {}", err, err),
+        }
+    }
+    let dump: Option<CkanDump<CkanVaccinesByAgePhu>> =
+        optional_dump(client, PHU_VACCINES_RESOURCE, PHU_VFNAME)?;
+    for r in dump.into_iter().flatten() {
+        match r.and_then(CkanVaccinesByAgePhu::into_doses) {
+            Ok((phu, d)) => {
+                if let Some(report) = days.get_mut(&(phu, d.date)) {
+                    report.doses.push(d);
+                }
+            }
+            Err(err) => println!("Error when iterating doses by phu {:?} {}", err, err),
+        }
+    }
+    let mut regions: BTreeMap<u32, (&'static Phu, Vec<RegionReport>)> = BTreeMap::new();
+    for ((id, _), report) in days {
+        if let Err(e) = report.validate() {
+            println!(
+                "Skipping invalid region report {} {} due to {:?}",
+                id,
+                report.key(),
+                e
+            );
+            continue;
+        }
+        if let Some(phu) = Phu::by_id(id) {
+            regions.entry(id).or_insert((phu, vec![])).1.push(report);
+        }
+    }
+    Ok(regions)
+}
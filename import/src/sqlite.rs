@@ -0,0 +1,245 @@
+use anyhow::{Context, Result};
+use ontariopublic::{DayReport, Index, Province, SERIES, SQL_SCHEMA, UNCHARTED_RATES};
+use rusqlite::{params, Connection, Transaction};
+use rust_decimal::prelude::*;
+
+//writes the reports of a province into the database at path, days already in it are replaced
+//so the database can be the canonical store across imports, the skipped days are the index's
+pub fn write(path: &str, province: &Province, reports: &[DayReport], index: &Index) -> Result<()> {
+    let mut conn =
+        Connection::open(path).with_context(|| format!("Failed to open database {}", path))?;
+    conn.execute_batch(SQL_SCHEMA)
+        .with_context(|| format!("Failed to create the tables in {}", path))?;
+    let tx = conn.transaction()?;
+    tx.execute(
+        "INSERT INTO import_runs (province, updated, days) VALUES (?1, ?2, ?3)",
+        params![province.code, index.updated.to_rfc3339(), reports.len()],
+    )?;
+    let run = tx.last_insert_rowid();
+    for r in reports {
+        write_report(&tx, province, r, run)
+            .with_context(|| format!("Failed to write {} to {}", r.key(), path))?;
+    }
+    //every import reads all the days again so its skipped days replace the previous ones
+    tx.execute(
+        "DELETE FROM skipped WHERE province = ?1",
+        params![province.code],
+    )?;
+    for (day, cause) in index.skipped() {
+        tx.execute(
+            "INSERT INTO skipped (province, date, cause, import_run) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                province.code,
+                day.format("%Y-%m-%d").to_string(),
+                cause.code(),
+                run
+            ],
+        )?;
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+fn write_report(tx: &Transaction, province: &Province, r: &DayReport, run: i64) -> Result<()> {
+    let date = r.cases.date.format("%Y-%m-%d").to_string();
+    //the whole report for the pages, the tables below are for queries
+    tx.execute(
+        "INSERT INTO reports (province, date, report, import_run) VALUES (?1, ?2, ?3, ?4)
+        ON CONFLICT (province, date) DO UPDATE SET
+            report = excluded.report,
+            import_run = excluded.import_run",
+        params![province.code, date, serde_json::to_string(r)?, run],
+    )?;
+    let c = &r.cases;
+    tx.execute(
+        "INSERT INTO cases (province, date, unvac, partial_vac, notfull_vac, full_vac, boost_vac, vac_unknown, import_run)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        ON CONFLICT (province, date) DO UPDATE SET
+            unvac = excluded.unvac,
+            partial_vac = excluded.partial_vac,
+            notfull_vac = excluded.notfull_vac,
+            full_vac = excluded.full_vac,
+            boost_vac = excluded.boost_vac,
+            vac_unknown = excluded.vac_unknown,
+            import_run = excluded.import_run",
+        params![
+            province.code,
+            date,
+            c.covid19_cases_unvac,
+            c.covid19_cases_partial_vac,
+            c.covid19_cases_notfull_vac,
+            c.covid19_cases_full_vac,
+            c.covid19_cases_boost_vac,
+            c.covid19_cases_vac_unknown,
+            run
+        ],
+    )?;
+    let h = &r.hosps;
+    tx.execute(
+        "INSERT INTO hospitalizations (province, date, icu_unvac, icu_partial_vac, icu_full_vac,
+            nonicu_unvac, nonicu_partial_vac, nonicu_full_vac, import_run)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        ON CONFLICT (province, date) DO UPDATE SET
+            icu_unvac = excluded.icu_unvac,
+            icu_partial_vac = excluded.icu_partial_vac,
+            icu_full_vac = excluded.icu_full_vac,
+            nonicu_unvac = excluded.nonicu_unvac,
+            nonicu_partial_vac = excluded.nonicu_partial_vac,
+            nonicu_full_vac = excluded.nonicu_full_vac,
+            import_run = excluded.import_run",
+        params![
+            province.code,
+            date,
+            h.icu_unvac,
+            h.icu_partial_vac,
+            h.icu_full_vac,
+            h.hospitalnonicu_unvac,
+            h.hospitalnonicu_partial_vac,
+            h.hospitalnonicu_full_vac,
+            run
+        ],
+    )?;
+    //a status the day has no rate for leaves no row, a revision that drops one removes it
+    tx.execute(
+        "DELETE FROM rates WHERE province = ?1 AND date = ?2",
+        params![province.code, date],
+    )?;
+    //the rates of the series registry, charted or not
+    //a charted rate is stored as its series rounds it, the chart read from D1 shows the kv values
+    //instead of rounding the float again where a decimal midpoint may have moved
+    let charted = SERIES.iter().map(|s| (&s.rate, s.value(r)));
+    let uncharted = UNCHARTED_RATES
+        .iter()
+        .map(|rate| (rate, (rate.per100k)(r).and_then(|d| d.to_f64())));
+    for (rate, per100k) in charted.chain(uncharted) {
+        if let Some(per100k) = per100k {
+            tx.execute(
+                "INSERT INTO rates (province, date, outcome, status, per100k, import_run)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    province.code,
+                    date,
+                    rate.outcome.name(),
+                    rate.doses.status(),
+                    per100k,
+                    run
+                ],
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use ontariopublic::{
+        index_from_rows, ChartRow, ChartSeries, DateRow, GapCause, Outcome, SkippedRow, UpdatedRow,
+        CHART_QUERY, INDEX_QUERY, ONTARIO, SKIPPED_QUERY, UPDATED_QUERY,
+    };
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd(2022, 1, d)
+    }
+
+    //the index the worker builds from D1 keeps the causes of the gaps like the one in kv
+    #[test]
+    fn skipped_days_round_trip() {
+        let path = std::env::temp_dir().join(format!("vax-skipped-{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let reports: Vec<DayReport> = [1, 5]
+            .iter()
+            .map(|&d| {
+                let mut r = DayReport::default();
+                r.cases.date = day(d);
+                r
+            })
+            .collect();
+        let index = Index::new(reports.iter().map(|r| r.cases.date)).with_skipped([
+            (day(2), GapCause::FailedValidation),
+            (day(3), GapCause::UnmatchedJoin),
+        ]);
+        write(path, ONTARIO, &reports, &index).unwrap();
+        //a later import without the failed day forgets it
+        let index = index.with_skipped([(day(3), GapCause::UnmatchedJoin)]);
+        write(path, ONTARIO, &reports, &index).unwrap();
+
+        let conn = Connection::open(path).unwrap();
+        let date = |s: String| NaiveDate::parse_from_str(&s, "%Y-%m-%d").unwrap();
+        let dates: Vec<DateRow> = conn
+            .prepare(INDEX_QUERY)
+            .unwrap()
+            .query_map(["on"], |row| {
+                Ok(DateRow {
+                    date: date(row.get(0)?),
+                })
+            })
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        let skipped: Vec<SkippedRow> = conn
+            .prepare(SKIPPED_QUERY)
+            .unwrap()
+            .query_map(["on"], |row| {
+                let cause: String = row.get(1)?;
+                Ok(SkippedRow {
+                    date: date(row.get(0)?),
+                    cause: serde_json::from_value(cause.into()).unwrap(),
+                })
+            })
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        let updated = conn
+            .query_row(UPDATED_QUERY, ["on"], |row| {
+                Ok(UpdatedRow {
+                    updated: row.get(0)?,
+                })
+            })
+            .unwrap();
+        drop(conn);
+        std::fs::remove_file(path).unwrap();
+        let read = index_from_rows(ONTARIO, &dates, &skipped, &updated)
+            .unwrap()
+            .unwrap();
+        assert_eq!(read.gaps(), index.gaps());
+        assert_eq!(read.cause(day(2)), Some(GapCause::MissingUpstream));
+        assert_eq!(read.cause(day(3)), Some(GapCause::UnmatchedJoin));
+    }
+
+    //a rate just under a midpoint becomes the midpoint as a float, the chart of D1 still
+    //rounds it down like the kv series
+    #[test]
+    fn charted_rates_round_like_kv() {
+        let path = std::env::temp_dir().join(format!("vax-rates-{}.db", std::process::id()));
+        let path = path.to_str().unwrap();
+        let mut r = DayReport::default();
+        r.cases.date = day(1);
+        r.cases.cases_full_vac_rate_per100k = Decimal::from_str("12.344999999999999999").unwrap();
+        assert_eq!(r.cases.cases_full_vac_rate_per100k.to_f64(), Some(12.345));
+        let kv = SERIES[1].value(&r);
+        assert_eq!(kv, Some(12.34));
+        write(path, ONTARIO, &[r], &Index::new([day(1)])).unwrap();
+
+        let conn = Connection::open(path).unwrap();
+        let rows: Vec<ChartRow> = conn
+            .prepare(CHART_QUERY)
+            .unwrap()
+            .query_map(params!["on", "cases", "2022-01-01", "2022-01-01"], |row| {
+                let date: String = row.get(0)?;
+                Ok(ChartRow {
+                    date: NaiveDate::parse_from_str(&date, "%Y-%m-%d").unwrap(),
+                    status: row.get(1)?,
+                    per100k: row.get(2)?,
+                })
+            })
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        drop(conn);
+        std::fs::remove_file(path).unwrap();
+        let chart = ChartSeries::from_rows(Outcome::Cases, rows);
+        assert_eq!(chart.lines[1], [kv]);
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use ontariopublic::{DayReport, Province};
+use serde::Serialize;
+use sha2::Sha256;
+use std::collections::HashMap;
+
+//header receivers check to make sure the payload came from us
+pub const SIGNATURE_HEADER: &str = "X-Vax-Signature";
+
+#[derive(Serialize, Debug, PartialEq)]
+pub struct Payload {
+    pub event: &'static str,
+    //code of the province the days are for
+    pub province: &'static str,
+    pub updated: String,
+    pub added: Vec<String>,
+    pub revised: Vec<String>,
+}
+
+impl Payload {
+    //compares day reports of the previous bulk file with the new ones
+    //both maps go from report key to its serialized report
+    pub fn diff(
+        province: &Province,
+        previous: &HashMap<String, String>,
+        current: &HashMap<String, String>,
+        updated: String,
+    ) -> Payload {
+        let mut added = vec![];
+        let mut revised = vec![];
+        for (key, value) in current {
+            match previous.get(key) {
+                None => added.push(key.clone()),
+                Some(old) if old != value => revised.push(key.clone()),
+                Some(_) => {}
+            }
+        }
+        added.sort_unstable();
+        revised.sort_unstable();
+        Payload {
+            event: "days_published",
+            province: province.code,
+            updated,
+            added,
+            revised,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.revised.is_empty()
+    }
+}
+
+//hex encoded hmac-sha256 of the body in the form sha256=<hex>
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("hmac accepts keys of any length");
+    mac.update(body);
+    let hex: String = mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("sha256={}", hex)
+}
+
+//posts the payload to every url, a failing receiver does not stop the others
+pub fn notify(urls: &[&str], secret: Option<&str>, payload: &Payload) -> Result<()> {
+    let body = serde_json::to_vec(payload)?;
+    let signature = secret.map(|s| sign(s, &body));
+    let mut failed = 0;
+    for url in urls {
+        let mut req = ureq::post(url).set("Content-Type", "application/json");
+        if let Some(sig) = &signature {
+            req = req.set(SIGNATURE_HEADER, sig);
+        }
+        match req.send_bytes(&body) {
+            Ok(_) => println!("Notified webhook {}", url),
+            Err(err) => {
+                println!("Error when calling webhook {} {}", url, err);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} of {} webhooks failed", failed, urls.len());
+    }
+    Ok(())
+}
+
+//loads the day reports of the province out of a previously written bulk file, keyed by day
+pub fn previous_reports(fname: &str, province: &Province) -> Result<HashMap<String, String>> {
+    #[derive(serde::Deserialize)]
+    struct Entry {
+        key: String,
+        value: String,
+        #[serde(default)]
+        base64: bool,
+    }
+    let f = match std::fs::File::open(fname) {
+        Ok(f) => f,
+        //first run there is nothing to compare with
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(err).with_context(|| format!("Failed to read from {}", fname)),
+    };
+    let entries: Vec<Entry> = serde_json::from_reader(std::io::BufReader::new(f))
+        .with_context(|| format!("Failed to deserialize {}", fname))?;
+    let prefix = province.key("");
+    let mut previous = HashMap::new();
+    for e in entries {
+        let day = match e.key.strip_prefix(&prefix) {
+            Some(day) if ontariopublic::key_date(day).is_some() => day.to_string(),
+            _ => continue,
+        };
+        //compact values are compared as the json they decode to
+        let value = if e.base64 {
+            let bytes = base64::decode(&e.value)
+                .with_context(|| format!("Failed to decode {} in {}", e.key, fname))?;
+            let report: DayReport = ontariopublic::decode(&bytes)?;
+            serde_json::to_string(&report)?
+        } else {
+            e.value
+        };
+        previous.insert(day, value);
+    }
+    Ok(previous)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ontariopublic::ONTARIO;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn diff_added_and_revised() {
+        let previous: HashMap<String, String> = [("20220101", "a"), ("20220102", "b")]
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        let current: HashMap<String, String> =
+            [("20220101", "a"), ("20220102", "c"), ("20220103", "d")]
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect();
+        let p = Payload::diff(ONTARIO, &previous, &current, "now".into());
+        assert_eq!(p.added, vec!["20220103"]);
+        assert_eq!(p.revised, vec!["20220102"]);
+        assert!(Payload::diff(ONTARIO, &current, &current, "now".into()).is_empty());
+    }
+
+    #[test]
+    fn notify_local_receiver() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let receiver = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = HashMap::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(": ") {
+                    headers.insert(name.to_ascii_lowercase(), value.to_string());
+                }
+            }
+            let len: usize = headers["content-length"].parse().unwrap();
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")
+                .unwrap();
+            (headers, body)
+        });
+        let payload = Payload {
+            event: "days_published",
+            province: "on",
+            updated: "2022-01-03T00:00:00+00:00".into(),
+            added: vec!["20220103".into()],
+            revised: vec![],
+        };
+        notify(&[url.as_str()], Some("secret"), &payload).unwrap();
+        let (headers, body) = receiver.join().unwrap();
+        assert_eq!(
+            headers[&SIGNATURE_HEADER.to_ascii_lowercase()],
+            sign("secret", &body)
+        );
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["added"][0], "20220103");
+        assert_eq!(json["event"], "days_published");
+    }
+}
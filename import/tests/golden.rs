@@ -0,0 +1,334 @@
+//runs the importer over the checked-in source files and compares bulk.json with golden files
+//run with UPDATE_GOLDEN=1 to rewrite the golden files after an intentional change
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SOURCES: [&str; 3] = [
+    "cases_by_vac_status.csv",
+    "cases_by_vac_status.json",
+    "hosp_by_vac_status.json",
+];
+//the index records when the import ran, pin it so the output is stable
+const UPDATED: &str = "2022-07-08T00:00:00Z";
+
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    key: String,
+    value: String,
+}
+
+//a scratch directory holding the checked-in sources, import reads them from its working directory
+fn source_dir(name: &str) -> PathBuf {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("vax-import-{}-{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for source in SOURCES {
+        fs::copy(manifest.join(source), dir.join(source)).unwrap();
+    }
+    dir
+}
+
+fn run_in(dir: &Path, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_import"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(status.success(), "import failed with {}", status);
+}
+
+fn run_import(name: &str, args: &[&str]) -> Vec<Entry> {
+    let dir = source_dir(name);
+    run_in(&dir, args);
+    let bulk = fs::read_to_string(dir.join("bulk.json")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let mut entries: Vec<Entry> = serde_json::from_str(&bulk).unwrap();
+    for entry in entries.iter_mut().filter(|e| e.key.ends_with("index")) {
+        let mut index: serde_json::Value = serde_json::from_str(&entry.value).unwrap();
+        index["updated"] = UPDATED.into();
+        entry.value = index.to_string();
+    }
+    entries
+}
+
+//one entry per line so a changed day shows up as a one line diff
+fn assert_golden(name: &str, entries: &[Entry]) {
+    let actual: String = entries
+        .iter()
+        .map(|e| serde_json::to_string(e).unwrap() + "\n")
+        .collect();
+    let golden: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, &actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&golden)
+        .unwrap_or_else(|e| panic!("{} {}, run with UPDATE_GOLDEN=1", golden.display(), e));
+    for (line, (a, e)) in actual.lines().zip(expected.lines()).enumerate() {
+        assert_eq!(a, e, "{} differs at line {}", name, line + 1);
+    }
+    assert_eq!(
+        actual.lines().count(),
+        expected.lines().count(),
+        "{} has a different number of entries",
+        name
+    );
+}
+
+#[test]
+fn bulk_from_csv() {
+    let entries = run_import("csv", &[]);
+    assert_golden("bulk_csv.jsonl", &entries);
+}
+
+#[test]
+fn bulk_from_json() {
+    let entries = run_import("json", &["--cases", "cases_by_vac_status.json"]);
+    assert_golden("bulk_json.jsonl", &entries);
+}
+
+//a province other than ontario from the long format csv, its keys are under p/<code>/
+#[test]
+fn bulk_from_status_csv() {
+    let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join("status_by_vac.csv");
+    let entries = run_import(
+        "status",
+        &[
+            "--province",
+            "qc",
+            "--status-csv",
+            fixture.to_str().unwrap(),
+        ],
+    );
+    assert!(entries.iter().all(|e| e.key.starts_with("p/qc/")));
+    assert_golden("bulk_status_qc.jsonl", &entries);
+}
+
+//a second import upserts the same days instead of adding rows
+#[test]
+fn sqlite_output_upserts() {
+    let dir = source_dir("sqlite");
+    run_in(&dir, &["--output", "sqlite:vax.db"]);
+    run_in(
+        &dir,
+        &["--output", "sqlite:vax.db", "--output", "bulk.json"],
+    );
+    let bulk: Vec<Entry> =
+        serde_json::from_str(&fs::read_to_string(dir.join("bulk.json")).unwrap()).unwrap();
+    let conn = rusqlite::Connection::open(dir.join("vax.db")).unwrap();
+    let count = |sql: &str| -> i64 { conn.query_row(sql, [], |row| row.get(0)).unwrap() };
+    let days = bulk
+        .iter()
+        .filter(|e| e.key.len() == 8 && e.key.bytes().all(|b| b.is_ascii_digit()))
+        .count() as i64;
+    assert!(days > 0);
+    assert_eq!(count("SELECT count(*) FROM import_runs"), 2);
+    assert_eq!(count("SELECT count(*) FROM cases"), days);
+    assert_eq!(count("SELECT count(*) FROM hospitalizations"), days);
+    assert_eq!(
+        count("SELECT count(DISTINCT date) FROM rates WHERE outcome = 'icu'"),
+        days
+    );
+    assert_eq!(count("SELECT count(*) FROM cases WHERE import_run != 2"), 0);
+    let first: String = conn
+        .query_row("SELECT min(date) FROM cases", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(first.len(), "2021-08-09".len());
+    drop(conn);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+//the queries the worker runs on D1 give the chart series import stores in kv
+#[test]
+fn sqlite_queries_match_bulk() {
+    let dir = source_dir("queries");
+    run_in(
+        &dir,
+        &["--output", "sqlite:vax.db", "--output", "bulk.json"],
+    );
+    let bulk: Vec<Entry> =
+        serde_json::from_str(&fs::read_to_string(dir.join("bulk.json")).unwrap()).unwrap();
+    let kv = |key: &str| -> serde_json::Value {
+        let entry = bulk.iter().find(|e| e.key == key).unwrap();
+        serde_json::from_str(&entry.value).unwrap()
+    };
+    let conn = rusqlite::Connection::open(dir.join("vax.db")).unwrap();
+    let dates: Vec<String> = conn
+        .prepare(ontariopublic::INDEX_QUERY)
+        .unwrap()
+        .query_map(["on"], |row| row.get(0))
+        .unwrap()
+        .map(|d| d.unwrap())
+        .collect();
+    let keys: Vec<String> = dates.iter().map(|d| d.replace('-', "")).collect();
+    assert_eq!(serde_json::json!(keys), kv("index")["keys"]);
+    for outcome in ontariopublic::Outcome::ALL {
+        let mut stmt = conn.prepare(ontariopublic::CHART_QUERY).unwrap();
+        let rows: Vec<ontariopublic::ChartRow> = stmt
+            .query_map(["on", outcome.name(), "0000-01-01", "9999-12-31"], |row| {
+                let date: String = row.get(0)?;
+                Ok(ontariopublic::ChartRow {
+                    date: date.parse().unwrap(),
+                    status: row.get(1)?,
+                    per100k: row.get(2)?,
+                })
+            })
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        let chart = ontariopublic::ChartSeries::from_rows(outcome, rows);
+        assert_eq!(serde_json::json!(chart.labels), kv("labels"));
+        for (series, line) in outcome.series().zip(&chart.lines) {
+            let stored = kv(series.key);
+            for (day, value) in line.iter().enumerate() {
+                //the rates table holds floats, a half may round the other way
+                let close = match (*value, stored[day].as_f64()) {
+                    (Some(a), Some(b)) => (a - b).abs() < 0.011,
+                    (a, b) => a == b,
+                };
+                assert!(close, "{} day {}", series.key, day);
+            }
+        }
+    }
+    drop(conn);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+//the compact encoding stores the same reports and index, base64 for wrangler
+#[test]
+fn bulk_with_cbor_encoding() {
+    #[derive(Deserialize)]
+    struct Encoded {
+        key: String,
+        value: String,
+        #[serde(default)]
+        base64: bool,
+    }
+    let dir = source_dir("cbor");
+    run_in(&dir, &["--encoding", "cbor"]);
+    let bulk: Vec<Encoded> =
+        serde_json::from_str(&fs::read_to_string(dir.join("bulk.json")).unwrap()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let golden: PathBuf = [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        "bulk_csv.jsonl",
+    ]
+    .iter()
+    .collect();
+    let expected: Vec<Entry> = fs::read_to_string(golden)
+        .unwrap()
+        .lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect();
+    assert_eq!(bulk.len(), expected.len());
+    assert!(bulk.iter().any(|e| e.base64));
+    for (e, g) in bulk.iter().zip(&expected) {
+        assert_eq!(e.key, g.key);
+        //plain text whatever the encoding
+        if e.key == ontariopublic::SNAPSHOT_POINTER {
+            assert_eq!(e.value, g.value);
+            continue;
+        }
+        let mut value: serde_json::Value = if e.base64 {
+            let bytes = base64::decode(&e.value).unwrap();
+            assert_eq!(bytes[0], ontariopublic::CBOR_VERSION);
+            ontariopublic::decode(&bytes).unwrap()
+        } else {
+            serde_json::from_str(&e.value).unwrap()
+        };
+        if e.key.ends_with("index") {
+            value["updated"] = UPDATED.into();
+        }
+        let golden: serde_json::Value = serde_json::from_str(&g.value).unwrap();
+        assert_eq!(value, golden, "{}", e.key);
+    }
+}
+
+//the snapshot holds the same days and chart series as the separate entries
+#[test]
+fn bulk_with_snapshot() {
+    #[derive(Deserialize)]
+    struct Encoded {
+        key: String,
+        value: String,
+        #[serde(default)]
+        base64: bool,
+    }
+    let dir = source_dir("snapshot");
+    run_in(&dir, &["--snapshot"]);
+    let bulk: Vec<Encoded> =
+        serde_json::from_str(&fs::read_to_string(dir.join("bulk.json")).unwrap()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let value = |key: &str| &bulk.iter().find(|e| e.key == key).unwrap().value;
+    let version = value(ontariopublic::SNAPSHOT_POINTER);
+    let blob = bulk
+        .iter()
+        .find(|e| e.key == format!("snapshot/{}", version))
+        .unwrap();
+    assert!(blob.base64);
+    let snapshot =
+        ontariopublic::Snapshot::from_bytes(&base64::decode(&blob.value).unwrap()).unwrap();
+    assert_eq!(&snapshot.version(), version);
+    for &day in snapshot.index.days() {
+        let key = ontariopublic::day_key(day);
+        let report: serde_json::Value = serde_json::from_str(value(&key)).unwrap();
+        assert_eq!(
+            serde_json::to_value(snapshot.report(day).unwrap()).unwrap(),
+            report,
+            "{}",
+            key
+        );
+    }
+    let labels: Vec<String> = serde_json::from_str(value("labels")).unwrap();
+    for outcome in ontariopublic::Outcome::ALL {
+        let chart = snapshot.chart(outcome, None, None).unwrap();
+        assert_eq!(chart.labels, labels);
+        for (series, line) in outcome.series().zip(&chart.lines) {
+            let stored: Vec<Option<f64>> = serde_json::from_str(value(series.key)).unwrap();
+            assert_eq!(line, &stored, "{}", series.key);
+        }
+    }
+}
+
+//wrangler only adds and replaces keys, a plain import after a snapshot one must still turn the snapshot off
+#[test]
+fn plain_import_clears_snapshot_pointer() {
+    let dir = source_dir("pointer");
+    let mut kv = std::collections::HashMap::new();
+    let mut load = |args: &[&str]| {
+        run_in(&dir, args);
+        let bulk: Vec<Entry> =
+            serde_json::from_str(&fs::read_to_string(dir.join("bulk.json")).unwrap()).unwrap();
+        kv.extend(bulk.into_iter().map(|e| (e.key, e.value)));
+        kv[ontariopublic::SNAPSHOT_POINTER].clone()
+    };
+    assert!(!load(&["--snapshot"]).is_empty());
+    assert_eq!(load(&[]), "");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+//a webhook that can't be reached leaves the previous bulk file so the next run notifies again
+#[test]
+fn failed_webhook_keeps_previous_bulk() {
+    let dir = source_dir("webhook");
+    fs::write(dir.join("bulk.json"), "[]").unwrap();
+    let status = Command::new(env!("CARGO_BIN_EXE_import"))
+        .args(["--webhook", "http://127.0.0.1:1/hook"])
+        .current_dir(&dir)
+        .output()
+        .unwrap()
+        .status;
+    assert!(!status.success());
+    assert_eq!(fs::read_to_string(dir.join("bulk.json")).unwrap(), "[]");
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,49 @@
+//what a page view pays to read the report and index out of kv, json against cbor
+//a native measurement, it ranks the encodings but the worker's wasm timings are not measured here
+use chrono::NaiveDate;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ontariopublic::{
+    decode, CasesByVacStatus, DayReport, Encoding, HospitalizationByVacStatus, Index,
+};
+use rust_decimal::Decimal;
+
+pub fn decode_benchmark(c: &mut Criterion) {
+    let keys: Vec<String> = (0..365)
+        .map(|d| {
+            (NaiveDate::from_ymd(2021, 8, 9) + chrono::Duration::days(d))
+                .format("%Y%m%d")
+                .to_string()
+        })
+        .collect();
+    let idx = Index::from(keys.as_slice());
+    let cases = CasesByVacStatus {
+        id: 33,
+        date: NaiveDate::from_ymd(2022, 1, 1),
+        covid19_cases_unvac: Some(4),
+        covid19_cases_partial_vac: Some(2),
+        covid19_cases_full_vac: 1,
+        covid19_cases_vac_unknown: Some(0),
+        cases_unvac_rate_per100k: Some(Decimal::new(4, 0)),
+        cases_partial_vac_rate_per100k: Some(Decimal::new(2, 0)),
+        cases_full_vac_rate_per100k: Decimal::new(1, 0),
+        cases_unvac_rate_7ma: Some(Decimal::new(4, 0)),
+        cases_partial_vac_rate_7ma: Some(Decimal::new(2, 0)),
+        cases_full_vac_rate_7ma: Some(Decimal::new(1, 0)),
+        ..Default::default()
+    };
+    let report = DayReport::from(cases, HospitalizationByVacStatus::default());
+
+    for (name, encoding) in [("json", Encoding::Json), ("cbor", Encoding::Cbor)] {
+        let report = encoding.encode(&report).unwrap();
+        let idx = encoding.encode(&idx).unwrap();
+        c.bench_function(&format!("decode report {}", name), |b| {
+            b.iter(|| decode::<DayReport>(black_box(&report)).unwrap())
+        });
+        c.bench_function(&format!("decode index {}", name), |b| {
+            b.iter(|| decode::<Index>(black_box(&idx)).unwrap())
+        });
+    }
+}
+
+criterion_group!(benches, decode_benchmark);
+criterion_main!(benches);
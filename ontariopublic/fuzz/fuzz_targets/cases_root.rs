@@ -0,0 +1,24 @@
+#![no_main]
+//feeds arbitrary bytes to the ckan json parsing, it must never panic and every record
+//it accepts must pass validation
+use libfuzzer_sys::fuzz_target;
+use ontariopublic::{CasesByVacStatusRoot, DataError};
+
+fuzz_target!(|data: &[u8]| {
+    let root: CasesByVacStatusRoot = match serde_json::from_slice(data) {
+        Ok(root) => root,
+        Err(_) => return,
+    };
+    let _ = root.validate();
+    for record in root {
+        match record {
+            Ok(cases) => assert!(cases.validate().is_ok()),
+            //only the required columns may fail to parse, optional ones turn into None
+            Err(DataError::Date(_))
+            | Err(DataError::Int(_))
+            | Err(DataError::Decimal(_))
+            | Err(DataError::Invalid(_)) => {}
+            Err(err) => panic!("unexpected error from a ckan record {:?}", err),
+        }
+    }
+});
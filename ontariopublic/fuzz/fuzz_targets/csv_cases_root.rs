@@ -0,0 +1,21 @@
+#![no_main]
+//feeds arbitrary bytes to the csv parsing, it must never panic and every record
+//it accepts must pass validation
+use libfuzzer_sys::fuzz_target;
+use ontariopublic::{CsvCase, CsvCasesRoot, DataError};
+
+fuzz_target!(|data: &[u8]| {
+    //rows the csv reader rejects are dropped the way import would stop on them
+    let records: Vec<CsvCase> = csv::Reader::from_reader(data)
+        .deserialize()
+        .filter_map(|r| r.ok())
+        .collect();
+    for record in CsvCasesRoot(records) {
+        match record {
+            Ok(cases) => assert!(cases.validate().is_ok()),
+            //numbers were already parsed by the csv reader, only the date and checks remain
+            Err(DataError::Date(_)) | Err(DataError::Invalid(_)) => {}
+            Err(err) => panic!("unexpected error from a csv record {:?}", err),
+        }
+    }
+});
@@ -0,0 +1,317 @@
+use crate::coverage::day;
+use crate::{DataError, Result};
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+//the 2021 census population of Ontario in the age groups the outcome datasets use
+//it is the reference population the age standardised rates are computed against
+pub static ONTARIO_2021: [(&str, i64); 6] = [
+    ("5-11", 1_106_389),
+    ("12-17", 982_323),
+    ("18-39", 4_113_233),
+    ("40-59", 3_805_975),
+    ("60-79", 2_972_620),
+    ("80+", 641_620),
+];
+
+//the datasets write the groups as 05-11yrs, 80+ or 80+yrs
+fn agegroup(v: &Value) -> String {
+    let s = v.as_str().unwrap_or_default().trim();
+    let s = s.strip_suffix("yrs").unwrap_or(s).trim();
+    match s.strip_prefix('0') {
+        Some(rest) if !rest.is_empty() => rest.to_string(),
+        _ => s.to_string(),
+    }
+}
+
+//rates are numbers in the dumps and text in the csv exports, empty before a status was tracked
+fn rate(v: &Value) -> Result<Option<Decimal>> {
+    match v {
+        Value::Null => Ok(None),
+        Value::Number(n) => Ok(Some(Decimal::from_str(&n.to_string())?)),
+        Value::String(s) if s.trim().is_empty() => Ok(None),
+        Value::String(s) => Ok(Some(Decimal::from_str(s.trim())?)),
+        _ => Err(DataError::Invalid(v.to_string())),
+    }
+}
+
+//a row of the cases by age and vaccination status dataset https://data.ontario.ca/dataset/covid-19-vaccine-data-in-ontario
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CkanCasesByAge {
+    date: Value,
+    agegroup: Value,
+    #[serde(rename = "cases_unvac_rate_per100K")]
+    unvac: Value,
+    #[serde(rename = "cases_notfull_vac_rate_per100K")]
+    notfull: Value,
+    #[serde(rename = "cases_full_vac_rate_per100K")]
+    full: Value,
+}
+
+impl TryFrom<CkanCasesByAge> for AgeRates {
+    type Error = DataError;
+
+    fn try_from(r: CkanCasesByAge) -> Result<Self> {
+        let v = AgeRates {
+            date: day(&r.date)?,
+            agegroup: agegroup(&r.agegroup),
+            unvac: rate(&r.unvac)?,
+            notfull: rate(&r.notfull)?,
+            full: rate(&r.full)?,
+        };
+        v.validate()?;
+        Ok(v)
+    }
+}
+
+//a row of the hospitalizations by age and vaccination status dataset, non icu and icu rates side by side
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CkanHospsByAge {
+    date: Value,
+    agegroup: Value,
+    #[serde(rename = "hospitalnonicu_unvac_rate_per100K")]
+    nonicu_unvac: Value,
+    #[serde(rename = "hospitalnonicu_notfull_vac_rate_per100K")]
+    nonicu_notfull: Value,
+    #[serde(rename = "hospitalnonicu_full_vac_rate_per100K")]
+    nonicu_full: Value,
+    #[serde(rename = "icu_unvac_rate_per100K")]
+    icu_unvac: Value,
+    #[serde(rename = "icu_notfull_vac_rate_per100K")]
+    icu_notfull: Value,
+    #[serde(rename = "icu_full_vac_rate_per100K")]
+    icu_full: Value,
+}
+
+impl CkanHospsByAge {
+    //the non icu and the icu rates of the row
+    pub fn into_rates(self) -> Result<[AgeRates; 2]> {
+        let date = day(&self.date)?;
+        let agegroup = agegroup(&self.agegroup);
+        let nonicu = AgeRates {
+            date,
+            agegroup: agegroup.clone(),
+            unvac: rate(&self.nonicu_unvac)?,
+            notfull: rate(&self.nonicu_notfull)?,
+            full: rate(&self.nonicu_full)?,
+        };
+        let icu = AgeRates {
+            date,
+            agegroup,
+            unvac: rate(&self.icu_unvac)?,
+            notfull: rate(&self.icu_notfull)?,
+            full: rate(&self.icu_full)?,
+        };
+        nonicu.validate()?;
+        icu.validate()?;
+        Ok([nonicu, icu])
+    }
+}
+
+//rates per 100,000 of one age group by vaccination status on a day
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct AgeRates {
+    pub date: NaiveDate,
+    pub agegroup: String,
+    pub unvac: Option<Decimal>,
+    pub notfull: Option<Decimal>,
+    pub full: Option<Decimal>,
+}
+
+impl AgeRates {
+    //checks the struct for sanity
+    pub fn validate(&self) -> Result<()> {
+        if self.agegroup.is_empty() {
+            return Err(DataError::Invalid("empty age group".into()));
+        }
+        for r in [self.unvac, self.notfull, self.full].into_iter().flatten() {
+            if r.is_sign_negative() {
+                return Err(DataError::Invalid(r.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn all(&self) -> Option<[Decimal; 3]> {
+        Some([self.unvac?, self.notfull?, self.full?])
+    }
+}
+
+//directly age standardised rates per 100,000 by vaccination status
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct StandardisedRates {
+    pub unvac: Decimal,
+    pub notfull: Decimal,
+    pub full: Decimal,
+}
+
+//weights the age specific rates by the reference population
+//every group of the reference needs a rate for each status, leaving a group out would weight
+//by another population and the result would not compare across days or provinces
+pub fn standardise(rates: &[AgeRates]) -> Option<StandardisedRates> {
+    let mut total = Decimal::zero();
+    let mut sums = [Decimal::zero(); 3];
+    for (group, population) in ONTARIO_2021 {
+        let values = rates
+            .iter()
+            .find(|r| r.agegroup == group)
+            .and_then(AgeRates::all)?;
+        let weight = Decimal::from(population);
+        total += weight;
+        for (sum, v) in sums.iter_mut().zip(values) {
+            *sum += v * weight;
+        }
+    }
+    let [unvac, notfull, full] = sums.map(|s| s / total);
+    Some(StandardisedRates {
+        unvac,
+        notfull,
+        full,
+    })
+}
+
+//the age specific rates published for a day
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq, Clone)]
+pub struct AgeReport {
+    #[serde(default)]
+    pub cases: Vec<AgeRates>,
+    #[serde(default)]
+    pub nonicu: Vec<AgeRates>,
+    #[serde(default)]
+    pub icu: Vec<AgeRates>,
+}
+
+impl AgeReport {
+    //checks the struct for sanity
+    pub fn validate(&self, date: NaiveDate) -> Result<()> {
+        for r in self.cases.iter().chain(&self.nonicu).chain(&self.icu) {
+            r.validate()?;
+            if r.date != date {
+                return Err(DataError::Invalid(
+                    "cases and age group dates do not match".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn standardised_cases(&self) -> Option<StandardisedRates> {
+        standardise(&self.cases)
+    }
+
+    pub fn standardised_nonicu(&self) -> Option<StandardisedRates> {
+        standardise(&self.nonicu)
+    }
+
+    pub fn standardised_icu(&self) -> Option<StandardisedRates> {
+        standardise(&self.icu)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CkanDump, HeaderField};
+
+    fn fields(ids: &[&str]) -> Vec<HeaderField> {
+        ids.iter()
+            .map(|id| HeaderField::new(id, "text", Default::default()))
+            .collect()
+    }
+
+    fn rates(agegroup: &str, unvac: i64, notfull: i64, full: Option<i64>) -> AgeRates {
+        AgeRates {
+            date: NaiveDate::from_ymd(2022, 1, 2),
+            agegroup: agegroup.into(),
+            unvac: Some(Decimal::from(unvac)),
+            notfull: Some(Decimal::from(notfull)),
+            full: full.map(Decimal::from),
+        }
+    }
+
+    #[test]
+    fn age_rates_from_dumps() {
+        let dump: CkanDump<CkanCasesByAge> = CkanDump::new(
+            fields(&[
+                "_id",
+                "date",
+                "agegroup",
+                "cases_unvac_rate_per100K",
+                "cases_notfull_vac_rate_per100K",
+                "cases_full_vac_rate_per100K",
+            ]),
+            vec![
+                serde_json::from_str(
+                    r#"[1, "2022-01-02T00:00:00", "05-11yrs", 120.5, "90.25", ""]"#,
+                )
+                .unwrap(),
+                serde_json::from_str(r#"[2, "2022-01-02", "80+", -1, 2, 3]"#).unwrap(),
+            ],
+        );
+        let rows: Vec<Result<AgeRates>> = dump
+            .into_iter()
+            .map(|r| r.and_then(AgeRates::try_from))
+            .collect();
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.agegroup, "5-11");
+        assert_eq!(first.unvac, Some(Decimal::new(1205, 1)));
+        assert_eq!(first.notfull, Some(Decimal::new(9025, 2)));
+        assert_eq!(first.full, None);
+        assert!(matches!(rows[1], Err(DataError::Invalid(_))));
+
+        let dump: CkanDump<CkanHospsByAge> = CkanDump::new(
+            fields(&[
+                "date",
+                "agegroup",
+                "hospitalnonicu_unvac_rate_per100K",
+                "icu_unvac_rate_per100K",
+            ]),
+            vec![serde_json::from_str(r#"["2022-01-02", "60-79yrs", 40, 8]"#).unwrap()],
+        );
+        let [nonicu, icu] = dump
+            .into_iter()
+            .next()
+            .unwrap()
+            .and_then(CkanHospsByAge::into_rates)
+            .unwrap();
+        assert_eq!(nonicu.agegroup, "60-79");
+        assert_eq!(nonicu.unvac, Some(Decimal::from(40)));
+        assert_eq!(icu.unvac, Some(Decimal::from(8)));
+        assert_eq!(icu.full, None);
+    }
+
+    #[test]
+    fn standardised_rates_weight_by_reference() {
+        assert_eq!(standardise(&[]), None);
+        //the same rate in every group is the standardised rate whatever the weights
+        let flat: Vec<AgeRates> = ONTARIO_2021
+            .iter()
+            .map(|(g, _)| rates(g, 100, 50, Some(10)))
+            .collect();
+        let s = standardise(&flat).unwrap();
+        assert_eq!(s.unvac, Decimal::from(100));
+        assert_eq!(s.full, Decimal::from(10));
+        //80+ is 641620 of the 13622160 people of the reference
+        let mut weighted: Vec<AgeRates> = ONTARIO_2021
+            .iter()
+            .map(|(g, _)| match *g {
+                "80+" => rates(g, 13_622_160, 0, Some(0)),
+                _ => rates(g, 0, 0, Some(0)),
+            })
+            .collect();
+        //groups outside of the reference are ignored
+        weighted.push(rates("Adults_18plus", 1000, 0, Some(0)));
+        let s = standardise(&weighted).unwrap();
+        assert_eq!(s.unvac, Decimal::from(641_620));
+        assert_eq!(s.full, Decimal::zero());
+        //a group without a rate for every status or missing altogether leaves no standardised rate
+        let mut partial = flat.clone();
+        partial[2].full = None;
+        assert_eq!(standardise(&partial), None);
+        assert_eq!(standardise(&flat[1..]), None);
+    }
+}
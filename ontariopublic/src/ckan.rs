@@ -0,0 +1,313 @@
+use crate::{DataError, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::marker::PhantomData;
+
+//a datastore dump as served by data.ontario.ca, the records are lists in the order of fields
+//each record is turned into T by field name so T only needs to derive Deserialize
+#[derive(Deserialize, Debug)]
+pub struct CkanDump<T> {
+    pub fields: Vec<HeaderField>,
+    pub records: Vec<Vec<Value>>,
+    #[serde(skip)]
+    row: PhantomData<T>,
+}
+
+impl<T> CkanDump<T> {
+    pub fn new(fields: Vec<HeaderField>, records: Vec<Vec<Value>>) -> Self {
+        CkanDump {
+            fields,
+            records,
+            row: PhantomData,
+        }
+    }
+
+    //compares the fields of the dump with the ones the row type was written for
+    pub fn check_fields(&self, expected: &[HeaderField]) -> Result<()> {
+        if self.fields.len() != expected.len() {
+            let msg = format!("the expected headers and received headers have different length expected: {} actual: {}", expected.len(), self.fields.len());
+            return Err(DataError::Problem(msg));
+        }
+        //ok if the same length zip together and compare
+        let zipped = self.fields.iter().zip(expected.iter());
+        for (a, b) in zipped {
+            if *a != *b {
+                let msg = format!(
+                    "The two header fields do not match left: {:?} right: {:?}",
+                    a, b
+                );
+                return Err(DataError::Problem(msg));
+            }
+        }
+        Ok(())
+    }
+}
+
+//pairs the values of a record with the field ids, missing trailing values are left out
+fn named_row<T: DeserializeOwned>(fields: &[HeaderField], record: Vec<Value>) -> Result<T> {
+    let row: Map<String, Value> = fields.iter().map(|f| f.id.clone()).zip(record).collect();
+    Ok(serde_json::from_value(Value::Object(row))?)
+}
+
+impl<T: DeserializeOwned> IntoIterator for CkanDump<T> {
+    type Item = Result<T>;
+    type IntoIter = CkanRows<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        CkanRows {
+            fields: self.fields,
+            records: self.records.into_iter(),
+            row: PhantomData,
+        }
+    }
+}
+
+pub struct CkanRows<T> {
+    fields: Vec<HeaderField>,
+    records: std::vec::IntoIter<Vec<Value>>,
+    row: PhantomData<T>,
+}
+
+impl<T: DeserializeOwned> Iterator for CkanRows<T> {
+    type Item = Result<T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records
+            .next()
+            .map(|record| named_row(&self.fields, record))
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq)]
+pub struct HeaderField {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    #[serde(default)]
+    pub info: HeaderFieldInfo,
+}
+
+impl HeaderField {
+    pub fn new(id: &str, field_type: &str, inf: HeaderFieldInfo) -> Self {
+        HeaderField {
+            id: id.to_string(),
+            field_type: field_type.to_string(),
+            info: inf,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Default, PartialEq)]
+pub struct HeaderFieldInfo {
+    notes: String,
+    type_override: String,
+    label: String,
+}
+
+impl HeaderFieldInfo {
+    pub fn new(notes: &str, type_override: &str, label: &str) -> Self {
+        HeaderFieldInfo {
+            notes: notes.to_string(),
+            type_override: type_override.to_string(),
+            label: label.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "client")]
+pub use client::DatastoreClient;
+
+#[cfg(feature = "client")]
+mod client {
+    use super::{CkanDump, HeaderField};
+    use crate::{DataError, Result};
+    use serde::Deserialize;
+    use serde_json::Value;
+
+    //what datastore_search wraps its result in
+    #[derive(Deserialize)]
+    struct SearchResponse {
+        success: bool,
+        result: Option<SearchResult>,
+        error: Option<Value>,
+    }
+
+    #[derive(Deserialize)]
+    struct SearchResult {
+        fields: Vec<HeaderField>,
+        records: Vec<Vec<Value>>,
+        total: Option<usize>,
+    }
+
+    //reads a datastore resource through the CKAN datastore_search api a page at a time
+    pub struct DatastoreClient {
+        base: String,
+        page_size: usize,
+        agent: ureq::Agent,
+    }
+
+    impl DatastoreClient {
+        //base is the site the api lives under e.g. https://data.ontario.ca
+        pub fn new(base: &str) -> Self {
+            DatastoreClient {
+                base: base.trim_end_matches('/').to_string(),
+                page_size: 1000,
+                agent: ureq::agent(),
+            }
+        }
+
+        pub fn page_size(mut self, page_size: usize) -> Self {
+            self.page_size = page_size.max(1);
+            self
+        }
+
+        //all the records of a resource, filters are field and value pairs that must match exactly
+        pub fn search<T>(
+            &self,
+            resource_id: &str,
+            filters: &[(&str, &str)],
+        ) -> Result<CkanDump<T>> {
+            let filters: serde_json::Map<String, Value> = filters
+                .iter()
+                .map(|(k, v)| (k.to_string(), Value::from(*v)))
+                .collect();
+            let filters = Value::Object(filters).to_string();
+            let url = format!("{}/api/3/action/datastore_search", self.base);
+            let mut fields = vec![];
+            let mut records = vec![];
+            loop {
+                let page = self.page(&url, resource_id, &filters, records.len())?;
+                let done = page.records.len() < self.page_size
+                    || page
+                        .total
+                        .is_some_and(|t| records.len() + page.records.len() >= t);
+                fields = page.fields;
+                records.extend(page.records);
+                if done {
+                    break;
+                }
+            }
+            Ok(CkanDump::new(fields, records))
+        }
+
+        fn page(
+            &self,
+            url: &str,
+            resource_id: &str,
+            filters: &str,
+            offset: usize,
+        ) -> Result<SearchResult> {
+            let body = self
+                .agent
+                .get(url)
+                .query("resource_id", resource_id)
+                .query("filters", filters)
+                .query("limit", &self.page_size.to_string())
+                .query("offset", &offset.to_string())
+                //lists keep the records in the same shape as the dumps
+                .query("records_format", "lists")
+                .call()
+                .map_err(|e| DataError::Http(e.to_string()))?
+                .into_string()
+                .map_err(|e| DataError::Http(e.to_string()))?;
+            let response: SearchResponse = serde_json::from_str(&body)?;
+            match (response.success, response.result) {
+                (true, Some(result)) => Ok(result),
+                _ => Err(DataError::Http(format!(
+                    "datastore_search failed {}",
+                    response.error.unwrap_or(Value::Null)
+                ))),
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::collections::HashMap;
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row {
+            #[serde(rename = "_id")]
+            id: i64,
+            phu: String,
+        }
+
+        //answers every request with a page of rows starting at the requested offset
+        fn serve(rows: usize, requests: usize) -> (String, std::thread::JoinHandle<Vec<String>>) {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let base = format!("http://{}", listener.local_addr().unwrap());
+            let handle = std::thread::spawn(move || {
+                let mut seen = vec![];
+                for _ in 0..requests {
+                    let (stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream);
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    loop {
+                        let mut header = String::new();
+                        reader.read_line(&mut header).unwrap();
+                        if header.trim_end().is_empty() {
+                            break;
+                        }
+                    }
+                    let target = line.split(' ').nth(1).unwrap().to_string();
+                    let query: HashMap<&str, &str> = target
+                        .split_once('?')
+                        .map(|(_, q)| q)
+                        .unwrap_or_default()
+                        .split('&')
+                        .filter_map(|kv| kv.split_once('='))
+                        .collect();
+                    let offset: usize = query["offset"].parse().unwrap();
+                    let limit: usize = query["limit"].parse().unwrap();
+                    let records: Vec<Value> = (offset..rows.min(offset + limit))
+                        .map(|i| serde_json::json!([i + 1, "Toronto"]))
+                        .collect();
+                    let body = serde_json::json!({
+                        "success": true,
+                        "result": {
+                            "fields": [{"id": "_id", "type": "int"}, {"id": "phu", "type": "text"}],
+                            "records": records,
+                            "total": rows,
+                        }
+                    })
+                    .to_string();
+                    write!(
+                        reader.get_mut(),
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                    .unwrap();
+                    seen.push(target);
+                }
+                seen
+            });
+            (base, handle)
+        }
+
+        #[test]
+        fn search_pages_and_filters() {
+            let (base, server) = serve(5, 3);
+            let client = DatastoreClient::new(&base).page_size(2);
+            let dump: CkanDump<Row> = client.search("abc", &[("phu", "Toronto")]).unwrap();
+            let rows: Vec<Row> = dump.into_iter().map(|r| r.unwrap()).collect();
+            assert_eq!(rows.len(), 5);
+            assert_eq!(
+                rows[4],
+                Row {
+                    id: 5,
+                    phu: "Toronto".into()
+                }
+            );
+            let seen = server.join().unwrap();
+            assert!(seen[0].contains("offset=0"));
+            assert!(seen[2].contains("offset=4"));
+            assert!(seen[0].contains("resource_id=abc"));
+            assert!(seen[0].contains("filters=%7B%22phu%22%3A%22Toronto%22%7D"));
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use crate::{DataError, Result};
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+//the age group Ontario publishes for everyone eligible, shown next to the outcome rates
+pub const ALL_ELIGIBLE: &str = "Ontario_5plus";
+
+//the datastore has these as numbers or as text depending on the resource
+pub(crate) fn count(v: &Value) -> Option<i64> {
+    match v {
+        Value::Number(n) => n.as_i64().or_else(|| n.as_f64().map(|f| f.round() as i64)),
+        Value::String(s) => s
+            .trim()
+            .replace(',', "")
+            .parse::<f64>()
+            .ok()
+            .map(|f| f.round() as i64),
+        _ => None,
+    }
+}
+
+//timestamps in the dumps, plain dates in the csv exports
+pub(crate) fn day(v: &Value) -> Result<NaiveDate> {
+    match v.as_str() {
+        Some(s) if s.len() > 10 => {
+            Ok(NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")?.date())
+        }
+        Some(s) => Ok(NaiveDate::parse_from_str(s, "%Y-%m-%d")?),
+        None => Err(DataError::Invalid(v.to_string())),
+    }
+}
+
+fn percent(part: i64, whole: i64) -> Decimal {
+    if whole == 0 {
+        return Decimal::zero();
+    }
+    Decimal::new(part, 0) * Decimal::ONE_HUNDRED / Decimal::new(whole, 0)
+}
+
+//a row of the vaccines by age dataset https://data.ontario.ca/dataset/covid-19-vaccine-data-in-ontario
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CkanVaccinesByAge {
+    #[serde(rename = "Date")]
+    date: Value,
+    #[serde(rename = "Agegroup")]
+    agegroup: Value,
+    #[serde(rename = "At least one dose_cumulative")]
+    at_least_one_dose: Value,
+    #[serde(rename = "Second_dose_cumulative")]
+    second_dose: Value,
+    #[serde(rename = "fully_vaccinated_cumulative")]
+    fully_vaccinated: Value,
+    #[serde(rename = "third_dose_cumulative")]
+    third_dose: Value,
+    #[serde(rename = "Total population")]
+    population: Value,
+}
+
+//cumulative doses administered to an age group up to a day
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct DosesByAge {
+    pub date: NaiveDate,
+    pub agegroup: String,
+    pub at_least_one_dose: i64,
+    pub fully_vaccinated: i64,
+    pub third_dose: Option<i64>,
+    pub population: i64,
+}
+
+impl DosesByAge {
+    //checks the struct for sanity
+    pub fn validate(&self) -> Result<()> {
+        if self.agegroup.is_empty() {
+            return Err(DataError::Invalid("empty age group".into()));
+        }
+        if self.population <= 0 {
+            return Err(DataError::Invalid(self.population.to_string()));
+        }
+        if self.at_least_one_dose < 0 || self.at_least_one_dose > self.population {
+            return Err(DataError::Invalid(self.at_least_one_dose.to_string()));
+        }
+        if self.fully_vaccinated < 0 || self.fully_vaccinated > self.at_least_one_dose {
+            return Err(DataError::Invalid(self.fully_vaccinated.to_string()));
+        }
+        if let Some(third) = self.third_dose {
+            if third < 0 || third > self.fully_vaccinated {
+                return Err(DataError::Invalid(third.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn at_least_one_dose_pct(&self) -> Decimal {
+        percent(self.at_least_one_dose, self.population)
+    }
+
+    pub fn fully_vaccinated_pct(&self) -> Decimal {
+        percent(self.fully_vaccinated, self.population)
+    }
+
+    pub fn third_dose_pct(&self) -> Option<Decimal> {
+        self.third_dose.map(|n| percent(n, self.population))
+    }
+}
+
+impl TryFrom<CkanVaccinesByAge> for DosesByAge {
+    type Error = DataError;
+
+    fn try_from(r: CkanVaccinesByAge) -> Result<Self> {
+        let required = |v: &Value| count(v).ok_or_else(|| DataError::Invalid(v.to_string()));
+        let at_least_one_dose = required(&r.at_least_one_dose)?;
+        let v = DosesByAge {
+            date: day(&r.date)?,
+            agegroup: r.agegroup.as_str().unwrap_or_default().trim().to_string(),
+            at_least_one_dose,
+            //fully vaccinated was only published separately once it stopped meaning two doses
+            fully_vaccinated: count(&r.fully_vaccinated)
+                .or_else(|| count(&r.second_dose))
+                .ok_or_else(|| DataError::Invalid(r.second_dose.to_string()))?,
+            third_dose: count(&r.third_dose),
+            population: required(&r.population)?,
+        };
+        v.validate()?;
+        Ok(v)
+    }
+}
+
+//a row of the status of cases dataset https://data.ontario.ca/dataset/status-of-covid-19-cases-in-ontario
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CkanCovidTesting {
+    #[serde(rename = "Reported Date")]
+    date: Value,
+    #[serde(rename = "Number of patients hospitalized with COVID-19")]
+    hospitalized: Value,
+    #[serde(rename = "Number of patients in ICU due to COVID-19")]
+    icu: Value,
+    #[serde(rename = "Number of patients in ICU on a ventilator due to COVID-19")]
+    icu_ventilator: Value,
+}
+
+//how many COVID-19 patients were in Ontario hospitals on a day regardless of vaccination status
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct Occupancy {
+    pub date: NaiveDate,
+    pub hospitalized: Option<i64>,
+    pub icu: Option<i64>,
+    pub icu_ventilator: Option<i64>,
+}
+
+impl Occupancy {
+    //checks the struct for sanity
+    pub fn validate(&self) -> Result<()> {
+        for n in [self.hospitalized, self.icu, self.icu_ventilator]
+            .into_iter()
+            .flatten()
+        {
+            if n < 0 {
+                return Err(DataError::Invalid(n.to_string()));
+            }
+        }
+        if let (Some(icu), Some(ventilator)) = (self.icu, self.icu_ventilator) {
+            if ventilator > icu {
+                return Err(DataError::Invalid(ventilator.to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    //share of the occupied beds that were in ICU
+    pub fn icu_pct(&self) -> Option<Decimal> {
+        match (self.icu, self.hospitalized) {
+            (Some(icu), Some(all)) if all > 0 => Some(percent(icu, all)),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<CkanCovidTesting> for Occupancy {
+    type Error = DataError;
+
+    fn try_from(r: CkanCovidTesting) -> Result<Self> {
+        let v = Occupancy {
+            date: day(&r.date)?,
+            hospitalized: count(&r.hospitalized),
+            icu: count(&r.icu),
+            icu_ventilator: count(&r.icu_ventilator),
+        };
+        v.validate()?;
+        Ok(v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CkanDump, HeaderField};
+
+    fn fields(ids: &[&str]) -> Vec<HeaderField> {
+        ids.iter()
+            .map(|id| HeaderField::new(id, "text", Default::default()))
+            .collect()
+    }
+
+    #[test]
+    fn doses_by_age_from_dump() {
+        //the order differs from the struct, rows are read by name
+        let dump: CkanDump<CkanVaccinesByAge> = CkanDump::new(
+            fields(&[
+                "_id",
+                "Agegroup",
+                "Date",
+                "Total population",
+                "At least one dose_cumulative",
+                "Second_dose_cumulative",
+                "third_dose_cumulative",
+            ]),
+            vec![
+                serde_json::from_str(
+                    r#"[1, "Ontario_5plus", "2022-01-02T00:00:00", 14000000, "12,000,000", 11000000, 5000000.0]"#,
+                )
+                .unwrap(),
+                serde_json::from_str(r#"[2, "80+", "2022-01-02", 100, 20, 30, null]"#).unwrap(),
+            ],
+        );
+        let rows: Vec<Result<DosesByAge>> = dump
+            .into_iter()
+            .map(|r| r.and_then(DosesByAge::try_from))
+            .collect();
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.date, NaiveDate::from_ymd(2022, 1, 2));
+        assert_eq!(first.agegroup, ALL_ELIGIBLE);
+        assert_eq!(first.at_least_one_dose, 12_000_000);
+        assert_eq!(first.fully_vaccinated, 11_000_000);
+        assert_eq!(first.third_dose, Some(5_000_000));
+        assert_eq!(
+            first.fully_vaccinated_pct().round_dp(2),
+            Decimal::new(7857, 2)
+        );
+        //more fully vaccinated than with one dose
+        assert!(matches!(rows[1], Err(DataError::Invalid(_))));
+    }
+
+    #[test]
+    fn occupancy_from_dump() {
+        let dump: CkanDump<CkanCovidTesting> = CkanDump::new(
+            fields(&[
+                "Reported Date",
+                "Number of patients hospitalized with COVID-19",
+                "Number of patients in ICU due to COVID-19",
+                "Number of patients in ICU on a ventilator due to COVID-19",
+            ]),
+            vec![
+                serde_json::from_str(r#"["2022-01-02T00:00:00", 2000, 300, null]"#).unwrap(),
+                serde_json::from_str(r#"["2020-03-01T00:00:00"]"#).unwrap(),
+                serde_json::from_str(r#"["2022-01-03T00:00:00", 2000, 300, 400]"#).unwrap(),
+            ],
+        );
+        let rows: Vec<Result<Occupancy>> = dump
+            .into_iter()
+            .map(|r| r.and_then(Occupancy::try_from))
+            .collect();
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.icu_pct(), Some(Decimal::new(15, 0)));
+        assert_eq!(first.icu_ventilator, None);
+        //before the columns existed
+        assert_eq!(rows[1].as_ref().unwrap().hospitalized, None);
+        assert!(matches!(rows[2], Err(DataError::Invalid(_))));
+    }
+}
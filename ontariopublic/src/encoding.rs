@@ -0,0 +1,112 @@
+use crate::{DataError, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+//first byte of a value in the compact encoding, json values start with { or [ so both can be told apart
+//a later change of the encoding gets the next number
+pub const CBOR_VERSION: u8 = 1;
+
+//how import stores a DayReport or an Index in kv
+//cbor keeps the field names, unlike bincode or postcard it is self describing so
+//the skip_serializing_if fields of the reports read back the same
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Json,
+    Cbor,
+}
+
+impl Encoding {
+    pub fn by_name(name: &str) -> Option<Encoding> {
+        match name {
+            "json" => Some(Encoding::Json),
+            "cbor" => Some(Encoding::Cbor),
+            _ => None,
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Encoding::Json => Ok(serde_json::to_vec(value)?),
+            Encoding::Cbor => {
+                let mut out = vec![CBOR_VERSION];
+                ciborium::ser::into_writer(value, &mut out)
+                    .map_err(|e| DataError::Encoding(e.to_string()))?;
+                Ok(out)
+            }
+        }
+    }
+}
+
+//reads a value in either encoding, values without a version byte are the json written before
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    match bytes.split_first() {
+        Some((&CBOR_VERSION, rest)) => {
+            ciborium::de::from_reader(rest).map_err(|e| DataError::Encoding(e.to_string()))
+        }
+        Some((first, _)) if !first.is_ascii() => Err(DataError::Encoding(format!(
+            "unknown format version {}",
+            first
+        ))),
+        _ => Ok(serde_json::from_slice(bytes)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AgeRates, AgeReport, DayReport, Index, Occupancy};
+    use chrono::NaiveDate;
+    use rust_decimal::Decimal;
+
+    fn report() -> DayReport {
+        let mut r = DayReport::default();
+        r.cases.date = NaiveDate::from_ymd(2022, 1, 2);
+        r.cases.covid19_cases_unvac = Some(300);
+        r.cases.cases_unvac_rate_per100k = Some(Decimal::new(3012345, 5));
+        r.cases.cases_full_vac_rate_per100k = Decimal::new(1, 1);
+        r.hosps.icu_unvac = 7;
+        r.occupancy = Some(Occupancy {
+            date: r.cases.date,
+            hospitalized: Some(4000),
+            icu: None,
+            icu_ventilator: Some(100),
+        });
+        r.ages = Some(AgeReport {
+            cases: vec![AgeRates {
+                date: r.cases.date,
+                agegroup: "5-11".into(),
+                unvac: Some(Decimal::new(1205, 1)),
+                notfull: None,
+                full: Some(Decimal::from(3)),
+            }],
+            ..Default::default()
+        });
+        r
+    }
+
+    #[test]
+    fn cbor_reads_back_like_json() {
+        let json = serde_json::to_string(&report()).unwrap();
+        let cbor = Encoding::Cbor.encode(&report()).unwrap();
+        assert_eq!(cbor[0], CBOR_VERSION);
+        assert!(cbor.len() < json.len());
+        let decoded: DayReport = decode(&cbor).unwrap();
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+        //values written before there was a version byte
+        let old: DayReport = decode(json.as_bytes()).unwrap();
+        assert_eq!(serde_json::to_string(&old).unwrap(), json);
+
+        let index = Index::from(&["20220101", "20220102"]);
+        let decoded: Index = decode(&Encoding::Cbor.encode(&index).unwrap()).unwrap();
+        assert_eq!(decoded.keys(), index.keys());
+        assert_eq!(decoded.updated, index.updated);
+
+        assert!(matches!(
+            decode::<Index>(&[0xff, 0]),
+            Err(DataError::Encoding(_))
+        ));
+        assert!(decode::<Index>(&[CBOR_VERSION, 0xff]).is_err());
+        assert_eq!(Encoding::by_name("cbor"), Some(Encoding::Cbor));
+        assert_eq!(Encoding::by_name("bincode"), None);
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+//why a day between the first and the last report has none
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GapCause {
+    //none of the sources had the day, hospitalizations are not published on weekends and holidays
+    MissingUpstream,
+    //the sources had the day but it did not pass validation
+    FailedValidation,
+    //only some of the sources a report is joined from had the day
+    UnmatchedJoin,
+}
+
+impl GapCause {
+    pub fn name(self) -> &'static str {
+        match self {
+            GapCause::MissingUpstream => "missing upstream",
+            GapCause::FailedValidation => "failed validation",
+            GapCause::UnmatchedJoin => "unmatched join",
+        }
+    }
+
+    //as serde writes it, the cause column of the sql skipped table
+    pub fn code(self) -> &'static str {
+        match self {
+            GapCause::MissingUpstream => "missing_upstream",
+            GapCause::FailedValidation => "failed_validation",
+            GapCause::UnmatchedJoin => "unmatched_join",
+        }
+    }
+}
+
+//a run of consecutive days without a report for the same reason
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    pub first: NaiveDate,
+    pub last: NaiveDate,
+    pub cause: GapCause,
+}
+
+impl Gap {
+    pub fn days(&self) -> i64 {
+        (self.last - self.first).num_days() + 1
+    }
+}
@@ -1,8 +1,40 @@
 use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use rust_decimal::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use thiserror::Error;
 
+mod ages;
+mod ckan;
+mod coverage;
+mod encoding;
+mod gaps;
+mod province;
+mod region;
+mod series;
+mod snapshot;
+mod sql;
+pub use ages::{
+    standardise, AgeRates, AgeReport, CkanCasesByAge, CkanHospsByAge, StandardisedRates,
+    ONTARIO_2021,
+};
+#[cfg(feature = "client")]
+pub use ckan::DatastoreClient;
+pub use ckan::{CkanDump, CkanRows, HeaderField, HeaderFieldInfo};
+pub use coverage::{CkanCovidTesting, CkanVaccinesByAge, DosesByAge, Occupancy, ALL_ELIGIBLE};
+pub use encoding::{decode, Encoding, CBOR_VERSION};
+pub use gaps::{Gap, GapCause};
+pub use province::{
+    reports_from_status_rows, Province, StatusCsvRow, VacStatus, ONTARIO, PROVINCES,
+};
+pub use region::{CkanCasesByPhu, CkanVaccinesByAgePhu, Phu, PhuCases, RegionReport, PHUS};
+pub use series::{rates, Doses, Outcome, Rate, Series, Transform, SERIES, UNCHARTED_RATES};
+pub use snapshot::{Snapshot, SNAPSHOT_POINTER};
+pub use sql::{
+    index_from_rows, ChartRow, ChartSeries, DateRow, ReportRow, SkippedRow, UpdatedRow,
+    CHART_QUERY, INDEX_QUERY, REPORT_QUERY, SKIPPED_QUERY, SQL_SCHEMA, UPDATED_QUERY,
+};
+
 const HUNDRED_K: Decimal = Decimal::from_parts(100000, 0, 0, false, 0);
 
 #[derive(Error, Debug)]
@@ -17,39 +49,59 @@ pub enum DataError {
     Decimal(#[from] rust_decimal::Error),
     #[error("{0}")]
     Problem(String),
+    #[error("unable to read json `{0}`")]
+    Json(#[from] serde_json::Error),
+    #[error("request failed `{0}`")]
+    Http(String),
+    #[error("unable to read or write the compact encoding `{0}`")]
+    Encoding(String),
+    //a source row whose date parsed but the rest of it did not
+    #[error("row of {date}: {source}")]
+    Row {
+        date: NaiveDate,
+        source: Box<DataError>,
+    },
+}
+
+impl DataError {
+    //the day of the row that failed, import records it as a gap
+    pub fn date(&self) -> Option<NaiveDate> {
+        match self {
+            DataError::Row { date, .. } => Some(*date),
+            _ => None,
+        }
+    }
+
+    fn on_day(self, date: Option<NaiveDate>) -> Self {
+        match date {
+            Some(date) => DataError::Row {
+                date,
+                source: Box::new(self),
+            },
+            None => self,
+        }
+    }
+}
+
+//the day of a datastore row, none when even that does not parse
+fn ckan_day(date: &serde_json::Value) -> Option<NaiveDate> {
+    let dt = NaiveDateTime::parse_from_str(date.as_str()?, "%Y-%m-%dT%H:%M:%S").ok()?;
+    Some(dt.date())
 }
 
 //just some alias for smaller function signatures
 pub type Result<T> = std::result::Result<T, DataError>;
 
 #[derive(Deserialize, Debug)]
-pub struct CasesByVacStatusRoot {
-    pub fields: Vec<HeaderField>,
-    pub records: Vec<Vec<serde_json::Value>>,
-}
+#[serde(transparent)]
+pub struct CasesByVacStatusRoot(pub CkanDump<CkanCases>);
 
 impl CasesByVacStatusRoot {
     pub fn validate(&self) -> Result<()> {
-        let expected = self.expected_header();
-        if self.fields.len() != expected.len() {
-            let msg = format!("the expected headers and received headers have different length expected: {} actual: {}", expected.len(), self.fields.len());
-            return Err(DataError::Problem(msg));
-        }
-        //ok if the same length zip together and compare
-        let zipped = self.fields.iter().zip(expected.iter());
-        for (a, b) in zipped {
-            if *a != *b {
-                let msg = format!(
-                    "The two header fields do not match left: {:?} right: {:?}",
-                    a, b
-                );
-                return Err(DataError::Problem(msg));
-            }
-        }
-        Ok(())
+        self.0.check_fields(&Self::expected_header())
     }
 
-    fn expected_header(&self) -> Vec<HeaderField> {
+    fn expected_header() -> Vec<HeaderField> {
         vec![
             HeaderField::new("_id", "int", Default::default()),
             HeaderField::new(
@@ -73,71 +125,42 @@ impl CasesByVacStatusRoot {
 
 impl IntoIterator for CasesByVacStatusRoot {
     type Item = Result<CasesByVacStatus>;
-    type IntoIter = CasesByVacStatusRootIterator;
+    type IntoIter = std::iter::Map<CkanRows<CkanCases>, fn(Result<CkanCases>) -> Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        CasesByVacStatusRootIterator {
-            root: self,
-            index: 0,
-        }
-    }
-}
-
-pub struct CasesByVacStatusRootIterator {
-    root: CasesByVacStatusRoot,
-    index: usize,
-}
-
-impl Iterator for CasesByVacStatusRootIterator {
-    type Item = Result<CasesByVacStatus>;
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.root.records.get(self.index) {
-            Some(rec) => {
-                self.index += 1;
-                Some(transform_record(rec))
-            }
-            None => None,
-        }
-    }
-}
-
-#[derive(Deserialize, Debug, Default, PartialEq)]
-pub struct HeaderField {
-    id: String,
-    #[serde(rename = "type")]
-    field_type: String,
-    #[serde(default)]
-    info: HeaderFieldInfo,
-}
-
-impl HeaderField {
-    fn new(id: &str, field_type: &str, inf: HeaderFieldInfo) -> Self {
-        HeaderField {
-            id: id.to_string(),
-            field_type: field_type.to_string(),
-            info: inf,
-        }
+        self.0.into_iter().map(|r| {
+            r.and_then(|c| {
+                let day = ckan_day(&c.date);
+                CasesByVacStatus::try_from(c).map_err(|e| e.on_day(day))
+            })
+        })
     }
 }
 
-#[derive(Deserialize, Debug, Default, PartialEq)]
-pub struct HeaderFieldInfo {
-    notes: String,
-    type_override: String,
-    label: String,
+//a row of the cases dump by field name, values stay json so the conversion decides what is lenient
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CkanCases {
+    #[serde(rename = "_id")]
+    id: serde_json::Value,
+    #[serde(rename = "Date")]
+    date: serde_json::Value,
+    covid19_cases_unvac: serde_json::Value,
+    covid19_cases_partial_vac: serde_json::Value,
+    covid19_cases_full_vac: serde_json::Value,
+    covid19_cases_vac_unknown: serde_json::Value,
+    #[serde(rename = "cases_unvac_rate_per100K")]
+    cases_unvac_rate_per100k: serde_json::Value,
+    #[serde(rename = "cases_partial_vac_rate_per100K")]
+    cases_partial_vac_rate_per100k: serde_json::Value,
+    #[serde(rename = "cases_full_vac_rate_per100K")]
+    cases_full_vac_rate_per100k: serde_json::Value,
+    cases_unvac_rate_7ma: serde_json::Value,
+    cases_partial_vac_rate_7ma: serde_json::Value,
+    cases_full_vac_rate_7ma: serde_json::Value,
 }
 
-impl HeaderFieldInfo {
-    fn new(notes: &str, type_override: &str, label: &str) -> Self {
-        HeaderFieldInfo {
-            notes: notes.to_string(),
-            type_override: type_override.to_string(),
-            label: label.to_string(),
-        }
-    }
-}
-
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct CasesByVacStatus {
     pub id: i64,
     pub date: NaiveDate,
@@ -213,32 +236,32 @@ impl CasesByVacStatus {
         };
         let zero = Decimal::new(0, 0);
         if let Some(unvac_rate_per100k) = self.cases_unvac_rate_per100k {
-            if unvac_rate_per100k < zero && unvac_rate_per100k > HUNDRED_K {
+            if unvac_rate_per100k < zero || unvac_rate_per100k > HUNDRED_K {
                 return Err(DataError::Invalid(unvac_rate_per100k.to_string()));
             }
         };
         if let Some(partial_vac_rate_per100k) = self.cases_partial_vac_rate_per100k {
-            if partial_vac_rate_per100k < zero && partial_vac_rate_per100k > HUNDRED_K {
+            if partial_vac_rate_per100k < zero || partial_vac_rate_per100k > HUNDRED_K {
                 return Err(DataError::Invalid(partial_vac_rate_per100k.to_string()));
             }
         };
-        if self.cases_full_vac_rate_per100k < zero && self.cases_full_vac_rate_per100k > HUNDRED_K {
+        if self.cases_full_vac_rate_per100k < zero || self.cases_full_vac_rate_per100k > HUNDRED_K {
             return Err(DataError::Invalid(
                 self.cases_full_vac_rate_per100k.to_string(),
             ));
         };
         if let Some(unvac_rate_7ma) = self.cases_unvac_rate_7ma {
-            if unvac_rate_7ma < zero && unvac_rate_7ma > HUNDRED_K {
+            if unvac_rate_7ma < zero || unvac_rate_7ma > HUNDRED_K {
                 return Err(DataError::Invalid(unvac_rate_7ma.to_string()));
             }
         }
         if let Some(partial_vac_rate_7ma) = self.cases_partial_vac_rate_7ma {
-            if partial_vac_rate_7ma < zero && partial_vac_rate_7ma > HUNDRED_K {
+            if partial_vac_rate_7ma < zero || partial_vac_rate_7ma > HUNDRED_K {
                 return Err(DataError::Invalid(partial_vac_rate_7ma.to_string()));
             }
         };
         if let Some(full_vac_rate_7ma) = self.cases_full_vac_rate_7ma {
-            if full_vac_rate_7ma < zero && full_vac_rate_7ma > HUNDRED_K {
+            if full_vac_rate_7ma < zero || full_vac_rate_7ma > HUNDRED_K {
                 return Err(DataError::Invalid(full_vac_rate_7ma.to_string()));
             }
         };
@@ -299,84 +322,64 @@ impl CasesByVacStatus {
 }
 
 //transfrorms a record from ontario source json into our usable type
-fn transform_record(record: &[serde_json::Value]) -> Result<CasesByVacStatus> {
-    let mut v: CasesByVacStatus = Default::default();
-    //get id
-    if let Some(idv) = record.get(0) {
-        if let Some(id) = idv.as_i64() {
+impl TryFrom<CkanCases> for CasesByVacStatus {
+    type Error = DataError;
+
+    fn try_from(r: CkanCases) -> Result<Self> {
+        let mut v: CasesByVacStatus = Default::default();
+        //get id
+        if let Some(id) = r.id.as_i64() {
             v.id = id;
         }
-    };
-    //get date
-    if let Some(datev) = record.get(1) {
-        if let Some(date) = datev.as_str() {
+        //get date
+        if let Some(date) = r.date.as_str() {
             let dt = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S")?;
             v.date = dt.date();
         }
-    };
-    //get cases unvac
-    if let Some(rv) = record.get(2) {
-        if let Some(snum) = rv.as_str() {
+        //get cases unvac
+        if let Some(snum) = r.covid19_cases_unvac.as_str() {
             v.covid19_cases_unvac = snum.parse::<i64>().ok();
         }
-    };
-    //get cases partial vac
-    if let Some(rv) = record.get(3) {
-        if let Some(snum) = rv.as_str() {
+        //get cases partial vac
+        if let Some(snum) = r.covid19_cases_partial_vac.as_str() {
             v.covid19_cases_partial_vac = snum.parse::<i64>().ok();
         }
-    };
-    //get cases full vac
-    if let Some(rv) = record.get(4) {
-        if let Some(snum) = rv.as_str() {
+        //get cases full vac
+        if let Some(snum) = r.covid19_cases_full_vac.as_str() {
             v.covid19_cases_full_vac = snum.parse::<i64>()?;
         }
-    };
-    //get cases vac unknown
-    if let Some(rv) = record.get(5) {
-        if let Some(snum) = rv.as_str() {
+        //get cases vac unknown
+        if let Some(snum) = r.covid19_cases_vac_unknown.as_str() {
             v.covid19_cases_vac_unknown = snum.parse::<i64>().ok();
         }
-    };
-    //get unvac rate
-    if let Some(rv) = record.get(6) {
-        if let Some(snum) = rv.as_str() {
+        //get unvac rate
+        if let Some(snum) = r.cases_unvac_rate_per100k.as_str() {
             v.cases_unvac_rate_per100k = Decimal::from_str(snum).map(Some)?;
         }
-    };
-    //get partial rate
-    if let Some(rv) = record.get(7) {
-        if let Some(snum) = rv.as_str() {
+        //get partial rate
+        if let Some(snum) = r.cases_partial_vac_rate_per100k.as_str() {
             v.cases_partial_vac_rate_per100k = Decimal::from_str(snum).map(Some)?;
         }
-    };
-    //get full rate
-    if let Some(rv) = record.get(8) {
-        if let Some(snum) = rv.as_str() {
+        //get full rate
+        if let Some(snum) = r.cases_full_vac_rate_per100k.as_str() {
             v.cases_full_vac_rate_per100k = Decimal::from_str(snum)?;
         }
-    };
-    //get unvac rate 7ma
-    if let Some(rv) = record.get(9) {
-        if let Some(snum) = rv.as_str() {
+        //get unvac rate 7ma
+        if let Some(snum) = r.cases_unvac_rate_7ma.as_str() {
             v.cases_unvac_rate_7ma = Decimal::from_str(snum).map(Some)?;
         }
-    };
-    //get partial rate 7ma
-    if let Some(rv) = record.get(10) {
-        if let Some(snum) = rv.as_str() {
+        //get partial rate 7ma
+        if let Some(snum) = r.cases_partial_vac_rate_7ma.as_str() {
             v.cases_partial_vac_rate_7ma = Decimal::from_str(snum).map(Some)?;
         }
-    };
-    //get full rate 7ma
-    if let Some(rv) = record.get(11) {
-        if let Some(snum) = rv.as_str() {
+        //get full rate 7ma
+        if let Some(snum) = r.cases_full_vac_rate_7ma.as_str() {
             v.cases_full_vac_rate_7ma = Decimal::from_str(snum).map(Some)?;
         }
-    };
 
-    let _ = v.validate()?;
-    Ok(v)
+        v.validate()?;
+        Ok(v)
+    }
 }
 
 fn compute_total_population_from_cases_and_rate(cases: i64, rate: Decimal) -> Decimal {
@@ -391,10 +394,18 @@ fn compute_total_population_from_cases_and_rate(cases: i64, rate: Decimal) -> De
     (case_count * HUNDRED_K) / rate
 }
 
-#[derive(Deserialize, Serialize, Debug, Default)]
+#[derive(Deserialize, Serialize, Debug, Default, Clone)]
 pub struct DayReport {
     pub cases: CasesByVacStatus,
     pub hosps: HospitalizationByVacStatus,
+    //vaccine coverage by age group published for the same day, empty when import did not have it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub doses: Vec<DosesByAge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub occupancy: Option<Occupancy>,
+    //age specific rates, the crude rates above mix very different age structures
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ages: Option<AgeReport>,
 }
 
 impl DayReport {
@@ -407,6 +418,25 @@ impl DayReport {
                 "cases and hospitalization dates do not match".into(),
             ));
         }
+        for doses in &self.doses {
+            doses.validate()?;
+            if doses.date != self.cases.date {
+                return Err(DataError::Invalid(
+                    "cases and doses dates do not match".into(),
+                ));
+            }
+        }
+        if let Some(occupancy) = &self.occupancy {
+            occupancy.validate()?;
+            if occupancy.date != self.cases.date {
+                return Err(DataError::Invalid(
+                    "cases and occupancy dates do not match".into(),
+                ));
+            }
+        }
+        if let Some(ages) = &self.ages {
+            ages.validate(self.cases.date)?;
+        }
         //test calculations
         if let Some(unvac_population) = self.cases.calc_unvac_population() {
             let num = (unvac_population
@@ -441,11 +471,20 @@ impl DayReport {
     }
 
     pub fn key(&self) -> String {
-        self.cases.date.format("%Y%m%d").to_string()
+        day_key(self.cases.date)
     }
 
     pub fn from(cases: CasesByVacStatus, hosps: HospitalizationByVacStatus) -> Self {
-        DayReport { cases, hosps }
+        DayReport {
+            cases,
+            hosps,
+            ..Default::default()
+        }
+    }
+
+    //the coverage of everyone eligible, the site shows it next to the rates
+    pub fn all_eligible_doses(&self) -> Option<&DosesByAge> {
+        self.doses.iter().find(|d| d.agegroup == ALL_ELIGIBLE)
     }
 
     pub fn icu_unvac_rate_per100k(&self) -> Option<Decimal> {
@@ -534,91 +573,228 @@ impl DayReport {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Index {
-    keys: Vec<String>,
+    //the days with a report in ascending order, stored as the keys of the reports like always
+    #[serde(rename = "keys", with = "day_keys")]
+    days: Vec<NaiveDate>,
     pub updated: DateTime<Utc>,
+    //code of the province the keys are days of, indexes from before there were others are ontario's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    province: Option<String>,
+    //days import read but left out, any other day without a report was not published
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    skipped: BTreeMap<NaiveDate, GapCause>,
 }
 
 impl Index {
-    pub fn from<T: AsRef<str>>(keys: &[T]) -> Self {
-        let mut v: Vec<String> = Vec::new();
-        for s in keys {
-            v.push(String::from(s.as_ref()));
-        }
-        v.sort_unstable();
+    pub fn new<I: IntoIterator<Item = NaiveDate>>(days: I) -> Self {
+        let mut days: Vec<NaiveDate> = days.into_iter().collect();
+        days.sort_unstable();
+        days.dedup();
         Index {
-            keys: v,
+            days,
             updated: Utc::now(),
+            province: None,
+            skipped: BTreeMap::new(),
         }
     }
 
-    pub fn most_recent(&self) -> String {
-        match self.keys.len() {
-            0 => String::from("error empty index"),
-            n => String::from(&self.keys[n - 1]),
+    //from the keys of DayReport::key, anything that is not a day key is left out
+    pub fn from<T: AsRef<str>>(keys: &[T]) -> Self {
+        Index::new(keys.iter().filter_map(|k| key_date(k.as_ref())))
+    }
+
+    pub fn with_province(mut self, province: &Province) -> Self {
+        self.province = if province.is_ontario() {
+            None
+        } else {
+            Some(province.code.to_string())
+        };
+        self
+    }
+
+    //why the days without a report were left out, only the days between the first and the last
+    //day with one are gaps, the others are dropped
+    pub fn with_skipped<I: IntoIterator<Item = (NaiveDate, GapCause)>>(
+        mut self,
+        skipped: I,
+    ) -> Self {
+        self.skipped = BTreeMap::new();
+        for (day, cause) in skipped {
+            if self.cause(day).is_some() {
+                self.skipped.insert(day, cause);
+            }
         }
+        self
+    }
+
+    //the days left out with their cause, as with_skipped kept them
+    pub fn skipped(&self) -> impl Iterator<Item = (NaiveDate, GapCause)> + '_ {
+        self.skipped.iter().map(|(day, cause)| (*day, *cause))
+    }
+
+    //unknown codes fall back to ontario like the keys without a prefix
+    pub fn province(&self) -> &'static Province {
+        self.province
+            .as_deref()
+            .and_then(Province::by_code)
+            .unwrap_or(ONTARIO)
+    }
+
+    pub fn first(&self) -> Option<NaiveDate> {
+        self.days.first().copied()
+    }
+
+    pub fn last(&self) -> Option<NaiveDate> {
+        self.days.last().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.days.len()
     }
 
-    pub fn next(&self, key: String) -> Option<String> {
-        match self.keys.binary_search(&key) {
-            Ok(idx) => self.keys.get(idx + 1).map(|v| v.to_string()),
-            Err(_) => None,
+    pub fn is_empty(&self) -> bool {
+        self.days.is_empty()
+    }
+
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.days.binary_search(&day).is_ok()
+    }
+
+    //the day with a report after day, none for the last or a day not in the index
+    pub fn next(&self, day: NaiveDate) -> Option<NaiveDate> {
+        let idx = self.idx(day)?;
+        self.get(idx + 1)
+    }
+
+    pub fn prev(&self, day: NaiveDate) -> Option<NaiveDate> {
+        let idx = self.idx(day)?;
+        self.get(idx.checked_sub(1)?)
+    }
+
+    pub fn get(&self, idx: usize) -> Option<NaiveDate> {
+        self.days.get(idx).copied()
+    }
+
+    pub fn idx(&self, day: NaiveDate) -> Option<usize> {
+        self.days.binary_search(&day).ok()
+    }
+
+    //all days in ascending order
+    pub fn days(&self) -> &[NaiveDate] {
+        &self.days
+    }
+
+    //all days as the keys their reports are stored under
+    pub fn keys(&self) -> Vec<String> {
+        self.days.iter().map(|d| day_key(*d)).collect()
+    }
+
+    //the days from first to last inclusive, an open end reaches the end of the index
+    pub fn range(&self, first: Option<NaiveDate>, last: Option<NaiveDate>) -> &[NaiveDate] {
+        let start = first.map_or(0, |f| self.days.partition_point(|d| *d < f));
+        let end = last.map_or(self.days.len(), |l| self.days.partition_point(|d| *d <= l));
+        self.days.get(start..end).unwrap_or_default()
+    }
+
+    //snaps a day that may not be present to the closest day we have data for
+    //when two days are equally far the earlier one wins
+    pub fn nearest(&self, day: NaiveDate) -> Option<NaiveDate> {
+        let pos = match self.days.binary_search(&day) {
+            Ok(idx) => return self.get(idx),
+            Err(pos) => pos,
+        };
+        let before = pos.checked_sub(1).and_then(|i| self.get(i));
+        let after = self.get(pos);
+        match (before, after) {
+            (Some(b), Some(a)) if (a - day) < (day - b) => Some(a),
+            (Some(b), _) => Some(b),
+            (None, after) => after,
         }
     }
 
-    pub fn prev(&self, key: String) -> Option<String> {
-        match self.keys.binary_search(&key) {
-            Ok(0) => None,
-            Ok(idx) => self.keys.get(idx - 1).map(|v| v.to_string()),
-            Err(_) => None,
+    //why there is no report for day, none when there is one or day is outside of the index
+    pub fn cause(&self, day: NaiveDate) -> Option<GapCause> {
+        match (self.first(), self.last()) {
+            (Some(first), Some(last)) if first < day && day < last && !self.contains(day) => Some(
+                self.skipped
+                    .get(&day)
+                    .copied()
+                    .unwrap_or(GapCause::MissingUpstream),
+            ),
+            _ => None,
         }
     }
 
-    pub fn max_idx(&self) -> usize {
-        self.keys.len() - 1
+    //the runs of days missing between the first and the last day, split where the cause changes
+    pub fn gaps(&self) -> Vec<Gap> {
+        let mut gaps: Vec<Gap> = vec![];
+        for w in self.days.windows(2) {
+            let mut day = w[0].succ();
+            while day < w[1] {
+                let cause = self.cause(day).unwrap_or(GapCause::MissingUpstream);
+                match gaps.last_mut() {
+                    Some(gap) if gap.cause == cause && gap.last.succ() == day => gap.last = day,
+                    _ => gaps.push(Gap {
+                        first: day,
+                        last: day,
+                        cause,
+                    }),
+                }
+                day = day.succ();
+            }
+        }
+        gaps
     }
+}
+
+//index keys are dates formatted as %Y%m%d see DayReport::key
+pub fn key_date(key: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(key, "%Y%m%d").ok()
+}
+
+pub fn day_key(day: NaiveDate) -> String {
+    day.format("%Y%m%d").to_string()
+}
+
+//the days of an index as the list of keys it was stored as before it held dates
+mod day_keys {
+    use super::{day_key, key_date};
+    use chrono::NaiveDate;
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-    pub fn get(&self, idx: usize) -> Option<String> {
-        self.keys.get(idx).map(|v| v.to_string())
+    pub fn serialize<S: Serializer>(days: &[NaiveDate], s: S) -> Result<S::Ok, S::Error> {
+        let keys: Vec<String> = days.iter().map(|d| day_key(*d)).collect();
+        keys.serialize(s)
     }
 
-    pub fn idx(&self, key: String) -> Option<usize> {
-        match self.keys.binary_search(&key) {
-            Ok(idx) => Some(idx),
-            Err(_) => None,
-        }
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<NaiveDate>, D::Error> {
+        let keys: Vec<String> = Vec::deserialize(d)?;
+        let mut days = keys
+            .iter()
+            .map(|k| {
+                key_date(k).ok_or_else(|| D::Error::custom(format!("`{}` is not a day key", k)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        //a key stored twice would throw off the positions next, prev and idx work with
+        days.sort_unstable();
+        days.dedup();
+        Ok(days)
     }
 }
 
 #[derive(Deserialize, Debug)]
-pub struct HospitalizationByVacStatusRoot {
-    pub fields: Vec<HeaderField>,
-    pub records: Vec<Vec<serde_json::Value>>,
-}
+#[serde(transparent)]
+pub struct HospitalizationByVacStatusRoot(pub CkanDump<CkanHosps>);
 
 impl HospitalizationByVacStatusRoot {
     pub fn validate(&self) -> Result<()> {
-        let expected = self.expected_header();
-        if self.fields.len() != expected.len() {
-            let msg = format!("the expected headers and received headers have different length expected: {} actual: {}", expected.len(), self.fields.len());
-            return Err(DataError::Problem(msg));
-        }
-        //ok if the same length zip together and compare
-        let zipped = self.fields.iter().zip(expected.iter());
-        for (a, b) in zipped {
-            if *a != *b {
-                let msg = format!(
-                    "The two header fields do not match left: {:?} right: {:?}",
-                    a, b
-                );
-                return Err(DataError::Problem(msg));
-            }
-        }
-        Ok(())
+        self.0.check_fields(&Self::expected_header())
     }
 
-    fn expected_header(&self) -> Vec<HeaderField> {
+    fn expected_header() -> Vec<HeaderField> {
         vec![
             HeaderField::new("_id", "int", Default::default()),
             HeaderField::new(
@@ -662,37 +838,36 @@ impl HospitalizationByVacStatusRoot {
 
 impl IntoIterator for HospitalizationByVacStatusRoot {
     type Item = Result<HospitalizationByVacStatus>;
-    type IntoIter = HospitalizationByVacStatusRootIterator;
+    type IntoIter = std::iter::Map<CkanRows<CkanHosps>, fn(Result<CkanHosps>) -> Self::Item>;
 
     fn into_iter(self) -> Self::IntoIter {
-        HospitalizationByVacStatusRootIterator {
-            root: self,
-            index: 0,
-        }
+        self.0.into_iter().map(|r| {
+            r.and_then(|h| {
+                let day = ckan_day(&h.date);
+                HospitalizationByVacStatus::try_from(h).map_err(|e| e.on_day(day))
+            })
+        })
     }
 }
 
-pub struct HospitalizationByVacStatusRootIterator {
-    root: HospitalizationByVacStatusRoot,
-    index: usize,
-}
-
-impl Iterator for HospitalizationByVacStatusRootIterator {
-    type Item = Result<HospitalizationByVacStatus>;
-    fn next(&mut self) -> Option<Self::Item> {
-        match self.root.records.get(self.index) {
-            Some(rec) => {
-                self.index += 1;
-                Some(transform_hosp_record(rec))
-            }
-            None => None,
-        }
-    }
+//a row of the hospitalization dump by field name
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CkanHosps {
+    #[serde(rename = "_id")]
+    id: serde_json::Value,
+    date: serde_json::Value,
+    icu_unvac: serde_json::Value,
+    icu_partial_vac: serde_json::Value,
+    icu_full_vac: serde_json::Value,
+    hospitalnonicu_unvac: serde_json::Value,
+    hospitalnonicu_partial_vac: serde_json::Value,
+    hospitalnonicu_full_vac: serde_json::Value,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
 pub struct HospitalizationByVacStatus {
-    id: i64,
+    pub id: i64,
     pub date: NaiveDate,
     pub icu_unvac: i64,
     pub icu_partial_vac: i64,
@@ -752,58 +927,42 @@ impl HospitalizationByVacStatus {
     }
 }
 
-fn transform_hosp_record(record: &[serde_json::Value]) -> Result<HospitalizationByVacStatus> {
-    let mut v: HospitalizationByVacStatus = Default::default();
-    //get id
-    if let Some(idv) = record.get(0) {
-        if let Some(id) = idv.as_i64() {
+impl TryFrom<CkanHosps> for HospitalizationByVacStatus {
+    type Error = DataError;
+
+    fn try_from(r: CkanHosps) -> Result<Self> {
+        let mut v: HospitalizationByVacStatus = Default::default();
+        //get id
+        if let Some(id) = r.id.as_i64() {
             v.id = id;
         }
-    };
-    //get date
-    if let Some(datev) = record.get(1) {
-        if let Some(date) = datev.as_str() {
+        //get date
+        if let Some(date) = r.date.as_str() {
             let dt = NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S")?;
             v.date = dt.date();
         }
-    };
-    if let Some(rv) = record.get(2) {
-        if let Some(snum) = rv.as_i64() {
+        if let Some(snum) = r.icu_unvac.as_i64() {
             v.icu_unvac = snum;
         }
-    };
-
-    if let Some(rv) = record.get(3) {
-        if let Some(snum) = rv.as_i64() {
+        if let Some(snum) = r.icu_partial_vac.as_i64() {
             v.icu_partial_vac = snum;
         }
-    };
-
-    if let Some(rv) = record.get(4) {
-        if let Some(snum) = rv.as_i64() {
+        if let Some(snum) = r.icu_full_vac.as_i64() {
             v.icu_full_vac = snum;
         }
-    };
-    if let Some(rv) = record.get(5) {
-        if let Some(snum) = rv.as_i64() {
+        if let Some(snum) = r.hospitalnonicu_unvac.as_i64() {
             v.hospitalnonicu_unvac = snum;
         }
-    };
-
-    if let Some(rv) = record.get(6) {
-        if let Some(snum) = rv.as_i64() {
+        if let Some(snum) = r.hospitalnonicu_partial_vac.as_i64() {
             v.hospitalnonicu_partial_vac = snum;
         }
-    };
-
-    if let Some(rv) = record.get(7) {
-        if let Some(snum) = rv.as_i64() {
+        if let Some(snum) = r.hospitalnonicu_full_vac.as_i64() {
             v.hospitalnonicu_full_vac = snum;
         }
-    };
 
-    let _ = v.validate()?;
-    Ok(v)
+        v.validate()?;
+        Ok(v)
+    }
 }
 
 //"Date","covid19_cases_unvac","covid19_cases_partial_vac","covid19_cases_full_vac","covid19_cases_vac_unknown","cases_unvac_rate_per100K","cases_partial_vac_rate_per100K","cases_full_vac_rate_per100K","cases_unvac_rate_7ma","cases_partial_vac_rate_7ma","cases_full_vac_rate_7ma"
@@ -858,7 +1017,9 @@ fn transform_csv_record(r: &CsvCase) -> Result<CasesByVacStatus> {
     v.cases_partial_vac_rate_7ma = r.cases_partial_vac_rate_7ma;
     v.cases_notfull_vac_rate_7ma = r.cases_notfull_vac_rate_7ma;
     v.cases_full_vac_rate_7ma = r.cases_full_vac_rate_7ma;
-    v.cases_boost_vac_rate_7ma = r.cases_boost_vac_rate_per100k;
+    v.cases_boost_vac_rate_7ma = r.cases_boost_vac_rate_7ma;
+    //same checks as records coming from the json source
+    v.validate()?;
     Ok(v)
 }
 
@@ -888,7 +1049,8 @@ impl Iterator for CsvCasesRootIterator {
         match self.records.get(self.index) {
             Some(rec) => {
                 self.index += 1;
-                Some(transform_csv_record(rec))
+                let day = NaiveDate::parse_from_str(&rec.date, "%Y-%m-%d").ok();
+                Some(transform_csv_record(rec).map_err(|e| e.on_day(day)))
             }
             None => None,
         }
@@ -898,6 +1060,9 @@ impl Iterator for CsvCasesRootIterator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::option;
+    use proptest::prelude::*;
+    use serde_json::Value;
 
     #[test]
     fn decimal_scale() {
@@ -943,30 +1108,354 @@ mod tests {
         );
     }
 
+    fn day(key: &str) -> NaiveDate {
+        key_date(key).unwrap()
+    }
+
     #[test]
     fn index_use() {
         let i = Index::from(&["20211201", "20211215"]);
-        let mr = i.most_recent();
-        assert_eq!("20211215", mr);
+        assert_eq!(Some(day("20211215")), i.last());
         let i = Index::from(&["20211201", "20211215", "20211130"]);
-        let mr = i.most_recent();
-        assert_eq!("20211215", mr);
+        assert_eq!(Some(day("20211215")), i.last());
+        assert_eq!(Some(day("20211130")), i.first());
+        assert_eq!(i.keys(), ["20211130", "20211201", "20211215"]);
+        let empty = Index::new([]);
+        assert!(empty.is_empty());
+        assert_eq!(None, empty.last());
+        assert_eq!(None, empty.first());
     }
 
     #[test]
     fn index_next_prev() {
         let i = Index::from(&["20211201", "20211215", "20211130"]);
-        let next = i.next("20211201".to_string());
-        let prev = i.prev("20211201".to_string());
-        assert_eq!(Some("20211215".to_string()), next);
-        assert_eq!(Some("20211130".to_string()), prev);
-        let next = i.next("20211130".to_string());
-        let prev = i.prev("20211130".to_string());
-        assert_eq!(Some("20211201".to_string()), next);
-        assert_eq!(None, prev);
-        let next = i.next("20211215".to_string());
-        let prev = i.prev("20211215".to_string());
-        assert_eq!(None, next);
-        assert_eq!(Some("20211201".to_string()), prev);
+        assert_eq!(Some(day("20211215")), i.next(day("20211201")));
+        assert_eq!(Some(day("20211130")), i.prev(day("20211201")));
+        assert_eq!(Some(day("20211201")), i.next(day("20211130")));
+        assert_eq!(None, i.prev(day("20211130")));
+        assert_eq!(None, i.next(day("20211215")));
+        assert_eq!(Some(day("20211201")), i.prev(day("20211215")));
+        assert_eq!(None, i.next(day("20211202")));
+    }
+
+    #[test]
+    fn index_nearest() {
+        let i = Index::from(&["20211201", "20211215", "20211130"]);
+        let nearest = |k: &str| i.nearest(day(k));
+        assert_eq!(Some(day("20211201")), nearest("20211201"));
+        assert_eq!(Some(day("20211130")), nearest("20210101"));
+        assert_eq!(Some(day("20211215")), nearest("20220101"));
+        assert_eq!(Some(day("20211201")), nearest("20211205"));
+        assert_eq!(Some(day("20211215")), nearest("20211212"));
+        //equally far snaps to the earlier day
+        assert_eq!(Some(day("20211201")), nearest("20211208"));
+        assert_eq!(None, Index::new([]).nearest(day("20211208")));
+    }
+
+    #[test]
+    fn index_range_and_gaps() {
+        let i = Index::from(&["20211130", "20211201", "20211202", "20211205", "20211215"]);
+        assert_eq!(i.range(None, None), i.days());
+        assert_eq!(
+            i.range(Some(day("20211201")), Some(day("20211205"))),
+            [day("20211201"), day("20211202"), day("20211205")]
+        );
+        assert_eq!(i.range(Some(day("20211203")), Some(day("20211204"))), []);
+        assert_eq!(i.range(Some(day("20211210")), Some(day("20211201"))), []);
+        assert_eq!(i.range(Some(day("20211214")), None), [day("20211215")]);
+        let gap = |first: &str, last: &str, cause| Gap {
+            first: day(first),
+            last: day(last),
+            cause,
+        };
+        assert_eq!(
+            i.gaps(),
+            [
+                gap("20211203", "20211204", GapCause::MissingUpstream),
+                gap("20211206", "20211214", GapCause::MissingUpstream)
+            ]
+        );
+        assert_eq!(i.gaps()[1].days(), 9);
+        assert!(Index::new([]).gaps().is_empty());
+        //the causes import recorded split the runs, days with a report or outside are not gaps
+        let i = i.with_skipped([
+            (day("20211204"), GapCause::FailedValidation),
+            (day("20211207"), GapCause::UnmatchedJoin),
+            (day("20211208"), GapCause::UnmatchedJoin),
+            (day("20211205"), GapCause::UnmatchedJoin),
+            (day("20211220"), GapCause::UnmatchedJoin),
+        ]);
+        assert_eq!(
+            i.gaps(),
+            [
+                gap("20211203", "20211203", GapCause::MissingUpstream),
+                gap("20211204", "20211204", GapCause::FailedValidation),
+                gap("20211206", "20211206", GapCause::MissingUpstream),
+                gap("20211207", "20211208", GapCause::UnmatchedJoin),
+                gap("20211209", "20211214", GapCause::MissingUpstream)
+            ]
+        );
+        assert_eq!(i.cause(day("20211205")), None);
+        assert_eq!(i.cause(day("20211220")), None);
+        let json = serde_json::to_string(&i).unwrap();
+        let read: Index = serde_json::from_str(&json).unwrap();
+        assert_eq!(read.gaps(), i.gaps());
+    }
+
+    #[test]
+    fn index_keeps_the_string_format() {
+        let json =
+            r#"{"keys":["20211215","20211201","20211215"],"updated":"2022-07-08T00:00:00Z"}"#;
+        let i: Index = serde_json::from_str(json).unwrap();
+        assert_eq!(i.days(), [day("20211201"), day("20211215")]);
+        assert_eq!(i.len(), 2);
+        assert_eq!(i.next(day("20211201")), Some(day("20211215")));
+        let out = serde_json::to_value(&i).unwrap();
+        assert_eq!(out["keys"], serde_json::json!(["20211201", "20211215"]));
+        assert!(serde_json::from_str::<Index>(
+            r#"{"keys":["labels"],"updated":"2022-07-08T00:00:00Z"}"#
+        )
+        .is_err());
+    }
+
+    //rates stay within the 0 to 100k validate accepts, with the scales the province uses
+    fn rate() -> impl Strategy<Value = Decimal> {
+        (0u32..=3).prop_flat_map(|scale| {
+            (0..=100_000 * 10i64.pow(scale)).prop_map(move |m| Decimal::new(m, scale))
+        })
+    }
+
+    fn count() -> impl Strategy<Value = i64> {
+        0i64..1_000_000
+    }
+
+    fn date() -> impl Strategy<Value = NaiveDate> {
+        (0i64..1500).prop_map(|d| NaiveDate::from_ymd(2020, 7, 1) + chrono::Duration::days(d))
+    }
+
+    prop_compose! {
+        //the ckan json only has the columns from before boosters were reported
+        fn ckan_cases()(
+            id in 1i64..100_000,
+            date in date(),
+            unvac in option::of(count()),
+            partial in option::of(count()),
+            full in count(),
+            unknown in option::of(count()),
+            unvac_rate in option::of(rate()),
+            partial_rate in option::of(rate()),
+            full_rate in rate(),
+            unvac_7ma in option::of(rate()),
+            partial_7ma in option::of(rate()),
+            full_7ma in option::of(rate()),
+        ) -> CasesByVacStatus {
+            CasesByVacStatus {
+                id,
+                date,
+                covid19_cases_unvac: unvac,
+                covid19_cases_partial_vac: partial,
+                covid19_cases_full_vac: full,
+                covid19_cases_vac_unknown: unknown,
+                cases_unvac_rate_per100k: unvac_rate,
+                cases_partial_vac_rate_per100k: partial_rate,
+                cases_full_vac_rate_per100k: full_rate,
+                cases_unvac_rate_7ma: unvac_7ma,
+                cases_partial_vac_rate_7ma: partial_7ma,
+                cases_full_vac_rate_7ma: full_7ma,
+                ..Default::default()
+            }
+        }
+    }
+
+    prop_compose! {
+        //the csv has every column but no id
+        fn csv_cases()(
+            base in ckan_cases(),
+            notfull in option::of(count()),
+            boost in option::of(count()),
+            notfull_rate in option::of(rate()),
+            boost_rate in option::of(rate()),
+            notfull_7ma in option::of(rate()),
+            boost_7ma in option::of(rate()),
+        ) -> CasesByVacStatus {
+            CasesByVacStatus {
+                id: 0,
+                covid19_cases_notfull_vac: notfull,
+                covid19_cases_boost_vac: boost,
+                cases_notfull_vac_rate_per100k: notfull_rate,
+                cases_boost_vac_rate_per100k: boost_rate,
+                cases_notfull_vac_rate_7ma: notfull_7ma,
+                cases_boost_vac_rate_7ma: boost_7ma,
+                ..base
+            }
+        }
+    }
+
+    prop_compose! {
+        fn hosps()(
+            id in 1i64..100_000,
+            date in date(),
+            counts in proptest::array::uniform6(0i64..10_000),
+        ) -> HospitalizationByVacStatus {
+            HospitalizationByVacStatus {
+                id,
+                date,
+                icu_unvac: counts[0],
+                icu_partial_vac: counts[1],
+                icu_full_vac: counts[2],
+                hospitalnonicu_unvac: counts[3],
+                hospitalnonicu_partial_vac: counts[4],
+                hospitalnonicu_full_vac: counts[5],
+            }
+        }
+    }
+
+    fn ckan_timestamp(date: NaiveDate) -> Value {
+        Value::from(date.format("%Y-%m-%dT00:00:00").to_string())
+    }
+
+    //ckan sends the case columns as text and null when there is no value
+    fn ckan_case_record(c: &CasesByVacStatus) -> Value {
+        let num = |n: Option<i64>| n.map_or(Value::Null, |n| Value::from(n.to_string()));
+        let dec = |d: Option<Decimal>| d.map_or(Value::Null, |d| Value::from(d.to_string()));
+        Value::from(vec![
+            Value::from(c.id),
+            ckan_timestamp(c.date),
+            num(c.covid19_cases_unvac),
+            num(c.covid19_cases_partial_vac),
+            num(Some(c.covid19_cases_full_vac)),
+            num(c.covid19_cases_vac_unknown),
+            dec(c.cases_unvac_rate_per100k),
+            dec(c.cases_partial_vac_rate_per100k),
+            dec(Some(c.cases_full_vac_rate_per100k)),
+            dec(c.cases_unvac_rate_7ma),
+            dec(c.cases_partial_vac_rate_7ma),
+            dec(c.cases_full_vac_rate_7ma),
+        ])
+    }
+
+    fn ckan_hosp_record(h: &HospitalizationByVacStatus) -> Value {
+        Value::from(vec![
+            Value::from(h.id),
+            ckan_timestamp(h.date),
+            Value::from(h.icu_unvac),
+            Value::from(h.icu_partial_vac),
+            Value::from(h.icu_full_vac),
+            Value::from(h.hospitalnonicu_unvac),
+            Value::from(h.hospitalnonicu_partial_vac),
+            Value::from(h.hospitalnonicu_full_vac),
+        ])
+    }
+
+    fn ckan_json(fields: Vec<HeaderField>, records: Vec<Value>) -> String {
+        serde_json::json!({ "fields": fields, "records": records }).to_string()
+    }
+
+    const CSV_HEADER: [&str; 17] = [
+        "Date",
+        "covid19_cases_unvac",
+        "covid19_cases_partial_vac",
+        "covid19_cases_notfull_vac",
+        "covid19_cases_full_vac",
+        "covid19_cases_boost_vac",
+        "covid19_cases_vac_unknown",
+        "cases_unvac_rate_per100K",
+        "cases_partial_vac_rate_per100K",
+        "cases_notfull_vac_rate_per100K",
+        "cases_full_vac_rate_per100K",
+        "cases_boost_vac_rate_per100K",
+        "cases_unvac_rate_7ma",
+        "cases_partial_vac_rate_7ma",
+        "cases_notfull_vac_rate_7ma",
+        "cases_full_vac_rate_7ma",
+        "cases_boost_vac_rate_7ma",
+    ];
+
+    fn csv_text(cases: &[CasesByVacStatus]) -> Vec<u8> {
+        let num = |n: Option<i64>| n.map_or(String::new(), |n| n.to_string());
+        let dec = |d: Option<Decimal>| d.map_or(String::new(), |d| d.to_string());
+        let mut w = csv::Writer::from_writer(vec![]);
+        w.write_record(CSV_HEADER).unwrap();
+        for c in cases {
+            w.write_record([
+                c.date.format("%Y-%m-%d").to_string(),
+                num(c.covid19_cases_unvac),
+                num(c.covid19_cases_partial_vac),
+                num(c.covid19_cases_notfull_vac),
+                num(Some(c.covid19_cases_full_vac)),
+                num(c.covid19_cases_boost_vac),
+                num(c.covid19_cases_vac_unknown),
+                dec(c.cases_unvac_rate_per100k),
+                dec(c.cases_partial_vac_rate_per100k),
+                dec(c.cases_notfull_vac_rate_per100k),
+                dec(Some(c.cases_full_vac_rate_per100k)),
+                dec(c.cases_boost_vac_rate_per100k),
+                dec(c.cases_unvac_rate_7ma),
+                dec(c.cases_partial_vac_rate_7ma),
+                dec(c.cases_notfull_vac_rate_7ma),
+                dec(c.cases_full_vac_rate_7ma),
+                dec(c.cases_boost_vac_rate_7ma),
+            ])
+            .unwrap();
+        }
+        w.into_inner().unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn ckan_cases_round_trip(cases in proptest::collection::vec(ckan_cases(), 0..20)) {
+            let fields = CasesByVacStatusRoot::expected_header();
+            let json = ckan_json(fields, cases.iter().map(ckan_case_record).collect());
+            let root: CasesByVacStatusRoot = serde_json::from_str(&json).unwrap();
+            root.validate().unwrap();
+            let parsed: Vec<CasesByVacStatus> = root.into_iter().map(|r| r.unwrap()).collect();
+            prop_assert_eq!(parsed, cases);
+        }
+
+        #[test]
+        fn ckan_hosps_round_trip(hosps in proptest::collection::vec(hosps(), 0..20)) {
+            let fields = HospitalizationByVacStatusRoot::expected_header();
+            let json = ckan_json(fields, hosps.iter().map(ckan_hosp_record).collect());
+            let root: HospitalizationByVacStatusRoot = serde_json::from_str(&json).unwrap();
+            root.validate().unwrap();
+            let parsed: Vec<HospitalizationByVacStatus> =
+                root.into_iter().map(|r| r.unwrap()).collect();
+            prop_assert_eq!(parsed, hosps);
+        }
+
+        #[test]
+        fn csv_cases_round_trip(cases in proptest::collection::vec(csv_cases(), 0..20)) {
+            let text = csv_text(&cases);
+            let records: Vec<CsvCase> = csv::Reader::from_reader(text.as_slice())
+                .deserialize()
+                .map(|r| r.unwrap())
+                .collect();
+            let parsed: Vec<CasesByVacStatus> =
+                CsvCasesRoot(records).into_iter().map(|r| r.unwrap()).collect();
+            prop_assert_eq!(parsed, cases);
+        }
+
+        //both sources reject a negative count with the same error
+        #[test]
+        fn negative_count_is_invalid(c in csv_cases(), negative in i64::MIN..0) {
+            let c = CasesByVacStatus { id: 1, covid19_cases_full_vac: negative, ..c };
+            let fields = CasesByVacStatusRoot::expected_header();
+            let json = ckan_json(fields, vec![ckan_case_record(&c)]);
+            let root: CasesByVacStatusRoot = serde_json::from_str(&json).unwrap();
+            let from_json = root.into_iter().next().unwrap();
+            let source = match from_json {
+                Err(DataError::Row { date, source }) if date == c.date => source,
+                other => panic!("{:?}", other),
+            };
+            prop_assert!(matches!(*source, DataError::Invalid(ref v) if *v == negative.to_string()));
+            let text = csv_text(&[c]);
+            let record: CsvCase = csv::Reader::from_reader(text.as_slice())
+                .deserialize()
+                .next()
+                .unwrap()
+                .unwrap();
+            let from_csv = transform_csv_record(&record);
+            prop_assert!(matches!(from_csv, Err(DataError::Invalid(ref v)) if *v == negative.to_string()));
+        }
     }
 }
@@ -0,0 +1,294 @@
+use crate::{CasesByVacStatus, DataError, DayReport, HospitalizationByVacStatus, Result};
+use chrono::NaiveDate;
+use rust_decimal::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+const HUNDRED_K: i64 = 100_000;
+
+//a province or territory the site can publish reports for
+#[derive(Debug, PartialEq)]
+pub struct Province {
+    //two letter postal abbreviation, lowercase in keys and urls
+    pub code: &'static str,
+    pub name: &'static str,
+    pub name_fr: &'static str,
+    //french needs the preposition that goes with the name
+    pub in_fr: &'static str,
+}
+
+const fn province(
+    code: &'static str,
+    name: &'static str,
+    name_fr: &'static str,
+    in_fr: &'static str,
+) -> Province {
+    Province {
+        code,
+        name,
+        name_fr,
+        in_fr,
+    }
+}
+
+pub static PROVINCES: [Province; 13] = [
+    province("on", "Ontario", "Ontario", "en Ontario"),
+    province("qc", "Quebec", "Québec", "au Québec"),
+    province(
+        "bc",
+        "British Columbia",
+        "Colombie-Britannique",
+        "en Colombie-Britannique",
+    ),
+    province("ab", "Alberta", "Alberta", "en Alberta"),
+    province("sk", "Saskatchewan", "Saskatchewan", "en Saskatchewan"),
+    province("mb", "Manitoba", "Manitoba", "au Manitoba"),
+    province(
+        "nb",
+        "New Brunswick",
+        "Nouveau-Brunswick",
+        "au Nouveau-Brunswick",
+    ),
+    province("ns", "Nova Scotia", "Nouvelle-Écosse", "en Nouvelle-Écosse"),
+    province(
+        "pe",
+        "Prince Edward Island",
+        "Île-du-Prince-Édouard",
+        "à l'Île-du-Prince-Édouard",
+    ),
+    province(
+        "nl",
+        "Newfoundland and Labrador",
+        "Terre-Neuve-et-Labrador",
+        "à Terre-Neuve-et-Labrador",
+    ),
+    province("yt", "Yukon", "Yukon", "au Yukon"),
+    province(
+        "nt",
+        "Northwest Territories",
+        "Territoires du Nord-Ouest",
+        "dans les Territoires du Nord-Ouest",
+    ),
+    province("nu", "Nunavut", "Nunavut", "au Nunavut"),
+];
+
+//the site started with ontario, its keys and urls have no prefix
+pub static ONTARIO: &Province = &PROVINCES[0];
+
+impl Province {
+    pub fn by_code(code: &str) -> Option<&'static Province> {
+        PROVINCES.iter().find(|p| p.code == code)
+    }
+
+    pub fn is_ontario(&self) -> bool {
+        self.code == ONTARIO.code
+    }
+
+    //where the pages of the province start, empty for ontario or e.g. /p/qc
+    pub fn prefix(&self) -> String {
+        if self.is_ontario() {
+            String::new()
+        } else {
+            format!("/p/{}", self.code)
+        }
+    }
+
+    //kv key of a report, index or chart series of the province
+    pub fn key(&self, k: &str) -> String {
+        if self.is_ontario() {
+            k.to_string()
+        } else {
+            format!("p/{}/{}", self.code, k)
+        }
+    }
+
+    //splits /p/<code>/rest into the province and /rest, other paths are ontario's
+    pub fn from_path(path: &str) -> (&'static Province, &str) {
+        let found = path.strip_prefix("/p/").and_then(|rest| {
+            let end = rest.find('/').unwrap_or(rest.len());
+            Province::by_code(&rest[..end]).map(|p| (p, &rest[end..]))
+        });
+        found.unwrap_or((ONTARIO, path))
+    }
+}
+
+//a row of the long format csv: one row per day and vaccination status
+//date,status,cases,population,hospitalnonicu,icu
+#[derive(Deserialize, Debug, Clone)]
+pub struct StatusCsvRow {
+    pub date: NaiveDate,
+    pub status: VacStatus,
+    pub cases: i64,
+    pub population: i64,
+    //empty on days hospitalizations were not reported
+    pub hospitalnonicu: Option<i64>,
+    pub icu: Option<i64>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum VacStatus {
+    Unvaccinated,
+    PartiallyVaccinated,
+    FullyVaccinated,
+    Boosted,
+}
+
+#[derive(Default, Clone, Copy)]
+struct StatusCounts {
+    cases: i64,
+    population: i64,
+    hospitalnonicu: i64,
+    icu: i64,
+}
+
+impl StatusCounts {
+    fn add(self, o: StatusCounts) -> StatusCounts {
+        StatusCounts {
+            cases: self.cases + o.cases,
+            population: self.population + o.population,
+            hospitalnonicu: self.hospitalnonicu + o.hospitalnonicu,
+            icu: self.icu + o.icu,
+        }
+    }
+
+    //per 100,000 like ontario publishes it, the population is read back from cases and rate
+    fn rate(&self) -> Result<Decimal> {
+        if self.population <= 0 {
+            return Err(DataError::Invalid(self.population.to_string()));
+        }
+        Ok(
+            (Decimal::from(self.cases) * Decimal::from(HUNDRED_K) / Decimal::from(self.population))
+                .round_dp(6),
+        )
+    }
+}
+
+//groups the rows by day into the same reports ontario's datasets produce
+//a day without a fully vaccinated row is invalid, the other statuses are optional
+pub fn reports_from_status_rows(rows: &[StatusCsvRow]) -> Vec<(NaiveDate, Result<DayReport>)> {
+    let mut days: BTreeMap<NaiveDate, BTreeMap<VacStatus, StatusCounts>> = BTreeMap::new();
+    for r in rows {
+        let counts = StatusCounts {
+            cases: r.cases,
+            population: r.population,
+            hospitalnonicu: r.hospitalnonicu.unwrap_or(0),
+            icu: r.icu.unwrap_or(0),
+        };
+        let day = days.entry(r.date).or_default();
+        let sum = day.get(&r.status).copied().unwrap_or_default().add(counts);
+        day.insert(r.status, sum);
+    }
+    days.into_iter()
+        .enumerate()
+        .map(|(i, (date, statuses))| (date, day_report(i as i64 + 1, date, &statuses)))
+        .collect()
+}
+
+fn day_report(
+    id: i64,
+    date: NaiveDate,
+    statuses: &BTreeMap<VacStatus, StatusCounts>,
+) -> Result<DayReport> {
+    let unvac = statuses.get(&VacStatus::Unvaccinated);
+    let partial = statuses.get(&VacStatus::PartiallyVaccinated);
+    let full = statuses
+        .get(&VacStatus::FullyVaccinated)
+        .ok_or_else(|| DataError::Problem(format!("no fully vaccinated row for {}", date)))?;
+    let boost = statuses.get(&VacStatus::Boosted);
+    let notfull = match (unvac, partial) {
+        (None, None) => None,
+        (a, b) => Some(
+            a.copied()
+                .unwrap_or_default()
+                .add(b.copied().unwrap_or_default()),
+        ),
+    };
+    let rate = |c: Option<&StatusCounts>| c.map(StatusCounts::rate).transpose();
+    let cases = CasesByVacStatus {
+        id,
+        date,
+        covid19_cases_unvac: unvac.map(|c| c.cases),
+        covid19_cases_partial_vac: partial.map(|c| c.cases),
+        covid19_cases_notfull_vac: notfull.map(|c| c.cases),
+        covid19_cases_full_vac: full.cases,
+        covid19_cases_boost_vac: boost.map(|c| c.cases),
+        cases_unvac_rate_per100k: rate(unvac)?,
+        cases_partial_vac_rate_per100k: rate(partial)?,
+        cases_notfull_vac_rate_per100k: rate(notfull.as_ref())?,
+        cases_full_vac_rate_per100k: full.rate()?,
+        cases_boost_vac_rate_per100k: rate(boost)?,
+        ..Default::default()
+    };
+    //ontario counts the boosted with the fully vaccinated in hospital
+    let full_hosps = full.add(boost.copied().unwrap_or_default());
+    let hosps = HospitalizationByVacStatus {
+        id,
+        date,
+        icu_unvac: unvac.map_or(0, |c| c.icu),
+        icu_partial_vac: partial.map_or(0, |c| c.icu),
+        icu_full_vac: full_hosps.icu,
+        hospitalnonicu_unvac: unvac.map_or(0, |c| c.hospitalnonicu),
+        hospitalnonicu_partial_vac: partial.map_or(0, |c| c.hospitalnonicu),
+        hospitalnonicu_full_vac: full_hosps.hospitalnonicu,
+    };
+    let report = DayReport::from(cases, hosps);
+    report.validate()?;
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ontario_keeps_the_root() {
+        let qc = Province::by_code("qc").unwrap();
+        assert_eq!(ONTARIO.key("index"), "index");
+        assert_eq!(ONTARIO.prefix(), "");
+        assert_eq!(qc.key("20220102"), "p/qc/20220102");
+        assert_eq!(qc.prefix(), "/p/qc");
+        assert_eq!(
+            Province::from_path("/p/qc/d/20220102/"),
+            (qc, "/d/20220102/")
+        );
+        assert_eq!(Province::from_path("/p/qc"), (qc, ""));
+        assert_eq!(Province::from_path("/p/xx/d/"), (ONTARIO, "/p/xx/d/"));
+        assert_eq!(
+            Province::from_path("/d/20220102/"),
+            (ONTARIO, "/d/20220102/")
+        );
+        for (i, p) in PROVINCES.iter().enumerate() {
+            assert!(PROVINCES[i + 1..].iter().all(|o| o.code != p.code));
+        }
+    }
+
+    #[test]
+    fn reports_from_long_rows() {
+        let csv = "date,status,cases,population,hospitalnonicu,icu
+2022-01-02,unvaccinated,300,1000000,40,10
+2022-01-02,partially_vaccinated,7,3000000,2,1
+2022-01-02,fully_vaccinated,500,9000000,50,5
+2022-01-02,boosted,100,2000000,10,1
+2022-01-03,unvaccinated,250,1000000,,
+";
+        let rows: Vec<StatusCsvRow> = csv::Reader::from_reader(csv.as_bytes())
+            .deserialize()
+            .map(|r| r.unwrap())
+            .collect();
+        let reports = reports_from_status_rows(&rows);
+        assert_eq!(reports.len(), 2);
+        let r = reports[0].1.as_ref().unwrap();
+        assert_eq!(r.cases.cases_unvac_rate_per100k, Some(Decimal::from(30)));
+        assert_eq!(r.cases.covid19_cases_notfull_vac, Some(307));
+        assert_eq!(
+            r.cases.calc_unvac_population(),
+            Some(Decimal::from(1_000_000))
+        );
+        assert_eq!(r.hosps.icu_full_vac, 6);
+        assert_eq!(r.icu_unvac_rate_per100k(), Some(Decimal::from(1)));
+        //a day needs the fully vaccinated
+        assert_eq!(reports[1].0, NaiveDate::from_ymd(2022, 1, 3));
+        assert!(matches!(reports[1].1, Err(DataError::Problem(_))));
+    }
+}
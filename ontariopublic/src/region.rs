@@ -0,0 +1,460 @@
+use crate::coverage::{count, day, CkanVaccinesByAge, DosesByAge, ALL_ELIGIBLE};
+use crate::{day_key, DataError, Result};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+//one of Ontario's public health units, id is the PHU ID used in the regional datasets
+#[derive(Debug, PartialEq, Eq)]
+pub struct Phu {
+    pub id: u32,
+    //used in urls and KV keys
+    pub slug: &'static str,
+    pub name: &'static str,
+    pub name_fr: &'static str,
+}
+
+const fn phu(id: u32, slug: &'static str, name: &'static str, name_fr: &'static str) -> Phu {
+    Phu {
+        id,
+        slug,
+        name,
+        name_fr,
+    }
+}
+
+//sorted by english name, the order of the region selector
+pub static PHUS: [Phu; 34] = [
+    phu(
+        2226,
+        "algoma",
+        "Algoma Public Health",
+        "Santé publique Algoma",
+    ),
+    phu(
+        2227,
+        "brant",
+        "Brant County Health Unit",
+        "Bureau de santé du comté de Brant",
+    ),
+    phu(
+        2240,
+        "chatham-kent",
+        "Chatham-Kent Public Health",
+        "Santé publique Chatham-Kent",
+    ),
+    phu(
+        2237,
+        "hamilton",
+        "City of Hamilton Public Health Services",
+        "Services de santé publique de la ville de Hamilton",
+    ),
+    phu(
+        2230,
+        "durham",
+        "Durham Region Health Department",
+        "Service de santé de la région de Durham",
+    ),
+    phu(
+        2258,
+        "eastern-ontario",
+        "Eastern Ontario Health Unit",
+        "Bureau de santé de l'est de l'Ontario",
+    ),
+    phu(
+        2233,
+        "grey-bruce",
+        "Grey Bruce Health Unit",
+        "Bureau de santé de Grey Bruce",
+    ),
+    phu(
+        2234,
+        "haldimand-norfolk",
+        "Haldimand-Norfolk Health Unit",
+        "Bureau de santé de Haldimand-Norfolk",
+    ),
+    phu(
+        2235,
+        "hkpr",
+        "Haliburton, Kawartha, Pine Ridge District Health Unit",
+        "Bureau de santé du district de Haliburton, Kawartha, Pine Ridge",
+    ),
+    phu(
+        2236,
+        "halton",
+        "Halton Region Public Health",
+        "Santé publique de la région de Halton",
+    ),
+    phu(
+        2238,
+        "hastings-prince-edward",
+        "Hastings Prince Edward Public Health",
+        "Santé publique Hastings Prince Edward",
+    ),
+    phu(
+        5183,
+        "huron-perth",
+        "Huron Perth Public Health",
+        "Santé publique Huron Perth",
+    ),
+    phu(
+        2241,
+        "kfla",
+        "Kingston, Frontenac and Lennox & Addington Public Health",
+        "Santé publique Kingston, Frontenac et Lennox et Addington",
+    ),
+    phu(
+        2242,
+        "lambton",
+        "Lambton Public Health",
+        "Santé publique Lambton",
+    ),
+    phu(
+        2243,
+        "leeds-grenville-lanark",
+        "Leeds, Grenville & Lanark District Health Unit",
+        "Bureau de santé du district de Leeds, Grenville et Lanark",
+    ),
+    phu(
+        2244,
+        "middlesex-london",
+        "Middlesex-London Health Unit",
+        "Bureau de santé de Middlesex-London",
+    ),
+    phu(
+        2246,
+        "niagara",
+        "Niagara Region Public Health",
+        "Santé publique de la région de Niagara",
+    ),
+    phu(
+        2247,
+        "north-bay-parry-sound",
+        "North Bay Parry Sound District Health Unit",
+        "Bureau de santé du district de North Bay-Parry Sound",
+    ),
+    phu(
+        2249,
+        "northwestern",
+        "Northwestern Health Unit",
+        "Bureau de santé du Nord-Ouest",
+    ),
+    phu(
+        2251,
+        "ottawa",
+        "Ottawa Public Health",
+        "Santé publique Ottawa",
+    ),
+    phu(2253, "peel", "Peel Public Health", "Santé publique Peel"),
+    phu(
+        2255,
+        "peterborough",
+        "Peterborough Public Health",
+        "Santé publique Peterborough",
+    ),
+    phu(
+        2256,
+        "porcupine",
+        "Porcupine Health Unit",
+        "Bureau de santé Porcupine",
+    ),
+    phu(
+        2261,
+        "sudbury",
+        "Public Health Sudbury & Districts",
+        "Santé publique Sudbury et districts",
+    ),
+    phu(
+        2265,
+        "waterloo",
+        "Region of Waterloo Public Health and Emergency Services",
+        "Santé publique et services d'urgence de la région de Waterloo",
+    ),
+    phu(
+        2257,
+        "renfrew",
+        "Renfrew County and District Health Unit",
+        "Bureau de santé du comté et du district de Renfrew",
+    ),
+    phu(
+        2260,
+        "simcoe-muskoka",
+        "Simcoe Muskoka District Health Unit",
+        "Bureau de santé du district de Simcoe Muskoka",
+    ),
+    phu(
+        4913,
+        "southwestern",
+        "Southwestern Public Health",
+        "Santé publique Sud-Ouest",
+    ),
+    phu(
+        2262,
+        "thunder-bay",
+        "Thunder Bay District Health Unit",
+        "Bureau de santé du district de Thunder Bay",
+    ),
+    phu(
+        2263,
+        "timiskaming",
+        "Timiskaming Health Unit",
+        "Bureau de santé du Timiskaming",
+    ),
+    phu(
+        3895,
+        "toronto",
+        "Toronto Public Health",
+        "Santé publique Toronto",
+    ),
+    phu(
+        2266,
+        "wellington-dufferin-guelph",
+        "Wellington-Dufferin-Guelph Public Health",
+        "Santé publique Wellington-Dufferin-Guelph",
+    ),
+    phu(
+        2268,
+        "windsor-essex",
+        "Windsor-Essex County Health Unit",
+        "Bureau de santé de Windsor-comté d'Essex",
+    ),
+    phu(
+        2270,
+        "york",
+        "York Region Public Health",
+        "Santé publique de la région de York",
+    ),
+];
+
+impl Phu {
+    pub fn by_id(id: u32) -> Option<&'static Phu> {
+        PHUS.iter().find(|p| p.id == id)
+    }
+
+    pub fn by_slug(slug: &str) -> Option<&'static Phu> {
+        PHUS.iter().find(|p| p.slug == slug)
+    }
+
+    //regional values live next to the provincial ones under r/<slug>/ e.g. r/toronto/index
+    pub fn key(&self, key: &str) -> String {
+        format!("r/{}/{}", self.slug, key)
+    }
+
+    fn from_value(v: &Value) -> Result<&'static Phu> {
+        count(v)
+            .and_then(|id| u32::try_from(id).ok())
+            .and_then(Phu::by_id)
+            .ok_or_else(|| DataError::Invalid(v.to_string()))
+    }
+}
+
+//a row of the cases by status and PHU dataset https://data.ontario.ca/dataset/status-of-covid-19-cases-in-ontario-by-public-health-unit-phu
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct CkanCasesByPhu {
+    #[serde(rename = "FILE_DATE")]
+    date: Value,
+    #[serde(rename = "PHU_NUM")]
+    phu: Value,
+    #[serde(rename = "ACTIVE_CASES")]
+    active: Value,
+    #[serde(rename = "RESOLVED_CASES")]
+    resolved: Value,
+    #[serde(rename = "DEATHS")]
+    deaths: Value,
+}
+
+//case counts a public health unit reported on a day, resolved and deaths are cumulative
+#[derive(Deserialize, Serialize, Debug, PartialEq, Clone)]
+pub struct PhuCases {
+    pub date: NaiveDate,
+    pub phu: u32,
+    pub active: i64,
+    pub resolved: i64,
+    pub deaths: i64,
+}
+
+impl PhuCases {
+    //checks the struct for sanity
+    pub fn validate(&self) -> Result<()> {
+        if Phu::by_id(self.phu).is_none() {
+            return Err(DataError::Invalid(self.phu.to_string()));
+        }
+        for n in [self.active, self.resolved, self.deaths] {
+            if n < 0 {
+                return Err(DataError::Invalid(n.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TryFrom<CkanCasesByPhu> for PhuCases {
+    type Error = DataError;
+
+    fn try_from(r: CkanCasesByPhu) -> Result<Self> {
+        let required = |v: &Value| count(v).ok_or_else(|| DataError::Invalid(v.to_string()));
+        let v = PhuCases {
+            date: day(&r.date)?,
+            phu: Phu::from_value(&r.phu)?.id,
+            active: required(&r.active)?,
+            resolved: required(&r.resolved)?,
+            deaths: required(&r.deaths)?,
+        };
+        v.validate()?;
+        Ok(v)
+    }
+}
+
+//a row of the vaccines by age and PHU dataset, the provincial columns plus the PHU
+#[derive(Deserialize, Debug, Default)]
+pub struct CkanVaccinesByAgePhu {
+    #[serde(rename = "PHU ID", default)]
+    phu: Value,
+    #[serde(flatten)]
+    doses: CkanVaccinesByAge,
+}
+
+impl CkanVaccinesByAgePhu {
+    pub fn into_doses(self) -> Result<(u32, DosesByAge)> {
+        let phu = Phu::from_value(&self.phu)?.id;
+        Ok((phu, DosesByAge::try_from(self.doses)?))
+    }
+}
+
+//what a public health unit published for a day, Ontario has no regional cases by vaccination status
+#[derive(Deserialize, Serialize, Debug, PartialEq)]
+pub struct RegionReport {
+    pub phu: u32,
+    pub date: NaiveDate,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cases: Option<PhuCases>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub doses: Vec<DosesByAge>,
+}
+
+impl RegionReport {
+    pub fn new(phu: u32, date: NaiveDate) -> Self {
+        RegionReport {
+            phu,
+            date,
+            cases: None,
+            doses: vec![],
+        }
+    }
+
+    //checks the structs for sanity
+    pub fn validate(&self) -> Result<()> {
+        if Phu::by_id(self.phu).is_none() {
+            return Err(DataError::Invalid(self.phu.to_string()));
+        }
+        if let Some(cases) = &self.cases {
+            cases.validate()?;
+            if cases.phu != self.phu || cases.date != self.date {
+                return Err(DataError::Invalid(
+                    "cases do not belong to the region report".into(),
+                ));
+            }
+        }
+        for doses in &self.doses {
+            doses.validate()?;
+            if doses.date != self.date {
+                return Err(DataError::Invalid(
+                    "region and doses dates do not match".into(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn key(&self) -> String {
+        day_key(self.date)
+    }
+
+    pub fn all_eligible_doses(&self) -> Option<&DosesByAge> {
+        self.doses.iter().find(|d| d.agegroup == ALL_ELIGIBLE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CkanDump, HeaderField};
+
+    #[test]
+    fn phus_are_unique() {
+        for (i, a) in PHUS.iter().enumerate() {
+            for b in &PHUS[i + 1..] {
+                assert_ne!(a.id, b.id);
+                assert_ne!(a.slug, b.slug);
+            }
+        }
+        assert_eq!(Phu::by_slug("toronto").map(|p| p.id), Some(3895));
+        assert_eq!(
+            Phu::by_id(3895).unwrap().key("20220102"),
+            "r/toronto/20220102"
+        );
+    }
+
+    #[test]
+    fn phu_rows_from_dump() {
+        let fields = |ids: &[&str]| -> Vec<HeaderField> {
+            ids.iter()
+                .map(|id| HeaderField::new(id, "text", Default::default()))
+                .collect()
+        };
+        let dump: CkanDump<CkanCasesByPhu> = CkanDump::new(
+            fields(&[
+                "_id",
+                "FILE_DATE",
+                "PHU_NAME",
+                "PHU_NUM",
+                "ACTIVE_CASES",
+                "RESOLVED_CASES",
+                "DEATHS",
+            ]),
+            vec![
+                serde_json::from_str(
+                    r#"[1, "2022-01-02T00:00:00", "TORONTO", 3895, 40000, 300000, 3800]"#,
+                )
+                .unwrap(),
+                serde_json::from_str(r#"[2, "2022-01-02T00:00:00", "NOWHERE", 1, 0, 0, 0]"#)
+                    .unwrap(),
+            ],
+        );
+        let rows: Vec<Result<PhuCases>> = dump
+            .into_iter()
+            .map(|r| r.and_then(PhuCases::try_from))
+            .collect();
+        assert_eq!(rows[0].as_ref().unwrap().active, 40000);
+        assert!(matches!(rows[1], Err(DataError::Invalid(_))));
+
+        let dump: CkanDump<CkanVaccinesByAgePhu> = CkanDump::new(
+            fields(&[
+                "Date",
+                "PHU ID",
+                "PHU name",
+                "Agegroup",
+                "At least one dose_cumulative",
+                "Second_dose_cumulative",
+                "Total population",
+            ]),
+            vec![serde_json::from_str(
+                r#"["2022-01-02T00:00:00", "3895", "TORONTO", "Ontario_5plus", 10, 9, 20]"#,
+            )
+            .unwrap()],
+        );
+        let (phu, doses) = dump
+            .into_iter()
+            .next()
+            .unwrap()
+            .and_then(CkanVaccinesByAgePhu::into_doses)
+            .unwrap();
+        assert_eq!(phu, 3895);
+        let mut report = RegionReport::new(phu, doses.date);
+        report.doses.push(doses);
+        report.cases = rows.into_iter().next().unwrap().ok();
+        assert!(report.validate().is_ok());
+        assert_eq!(report.all_eligible_doses().unwrap().fully_vaccinated, 9);
+    }
+}
@@ -0,0 +1,365 @@
+use crate::DayReport;
+use rust_decimal::prelude::*;
+
+//what a chart page is about, each draws the series of its outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    Cases,
+    NonIcu,
+    Icu,
+}
+
+impl Outcome {
+    //in the order of the chart pages, SERIES lists the series of each in the same order
+    pub const ALL: [Outcome; 3] = [Outcome::Cases, Outcome::NonIcu, Outcome::Icu];
+
+    //the outcome of the rates table
+    pub fn name(self) -> &'static str {
+        match self {
+            Outcome::Cases => "cases",
+            Outcome::NonIcu => "nonicu",
+            Outcome::Icu => "icu",
+        }
+    }
+
+    //the chart page is /ch/<slug>/
+    pub fn slug(self) -> &'static str {
+        match self {
+            Outcome::Cases => "ca",
+            Outcome::NonIcu => "ni",
+            Outcome::Icu => "ii",
+        }
+    }
+
+    pub fn by_slug(slug: &str) -> Option<Outcome> {
+        Outcome::ALL.into_iter().find(|o| o.slug() == slug)
+    }
+
+    pub fn path(self) -> String {
+        format!("/ch/{}/", self.slug())
+    }
+
+    pub fn series(self) -> impl Iterator<Item = &'static Series> {
+        SERIES.iter().filter(move |s| s.outcome() == self)
+    }
+}
+
+//the vaccination status of a rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Doses {
+    Zero,
+    //at least one dose but not two
+    Partial,
+    Two,
+    //zero or one dose, what the partial and unvaccinated counts add up to
+    LessThanTwo,
+    Boosted,
+}
+
+impl Doses {
+    //the status of the rates table
+    pub fn status(self) -> &'static str {
+        match self {
+            Doses::Zero => "unvac",
+            Doses::Partial => "partial_vac",
+            Doses::Two => "full_vac",
+            Doses::LessThanTwo => "notfull_vac",
+            Doses::Boosted => "boost_vac",
+        }
+    }
+}
+
+//a rate per 100k of a day report, as published or derived from the counts and populations
+#[derive(Debug, Clone, Copy)]
+pub struct Rate {
+    pub outcome: Outcome,
+    pub doses: Doses,
+    //none when the day did not publish it
+    pub per100k: fn(&DayReport) -> Option<Decimal>,
+}
+
+//rates the sql store keeps that no chart draws, the rates table holds these and those of SERIES
+pub const UNCHARTED_RATES: [Rate; 4] = [
+    Rate {
+        outcome: Outcome::Cases,
+        doses: Doses::Partial,
+        per100k: |r| r.cases.cases_partial_vac_rate_per100k,
+    },
+    Rate {
+        outcome: Outcome::Cases,
+        doses: Doses::Boosted,
+        per100k: |r| r.cases.cases_boost_vac_rate_per100k,
+    },
+    Rate {
+        outcome: Outcome::NonIcu,
+        doses: Doses::Partial,
+        per100k: |r| r.nonicu_partial_vac_rate_per100k(),
+    },
+    Rate {
+        outcome: Outcome::Icu,
+        doses: Doses::Partial,
+        per100k: |r| r.icu_partial_vac_rate_per100k(),
+    },
+];
+
+//every rate import writes to the rates table
+pub fn rates() -> impl Iterator<Item = &'static Rate> {
+    SERIES.iter().map(|s| &s.rate).chain(&UNCHARTED_RATES)
+}
+
+//how a rate becomes a value of a chart series, halves are rounded away from zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transform {
+    //a fixed number of places, fine while the rates are well above one
+    DecimalPlaces(u32),
+    //the first digits whatever the scale, small rates keep their shape instead of rounding to 0
+    SignificantFigures(u32),
+}
+
+impl Transform {
+    //none when the rate can't be rounded or stored as a float, a chart shows that as a gap
+    //where a zero would be a value that was never published
+    pub fn apply(self, rate: Decimal) -> Option<f64> {
+        let rounded = match self {
+            Transform::DecimalPlaces(dp) => {
+                Some(rate.round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero))
+            }
+            //rust_decimal gives zero for no figures at all
+            Transform::SignificantFigures(0) => None,
+            Transform::SignificantFigures(sf) => {
+                rate.round_sf_with_strategy(sf, RoundingStrategy::MidpointAwayFromZero)
+            }
+        };
+        rounded?.normalize().to_f64()
+    }
+
+    //the places a value is shown with, 2.5 with two places reads 2.50 like the values around it
+    pub fn places(self, value: f64) -> u32 {
+        match self {
+            Transform::DecimalPlaces(dp) => dp,
+            Transform::SignificantFigures(sf) if value != 0.0 && value.is_finite() => {
+                let magnitude = value.abs().log10().floor() as i64;
+                (sf as i64 - 1 - magnitude).max(0) as u32
+            }
+            Transform::SignificantFigures(sf) => sf.saturating_sub(1),
+        }
+    }
+
+    //a value that is already a float, the days interpolated fills in between two reports
+    pub fn apply_f64(self, rate: f64) -> Option<f64> {
+        Decimal::from_f64(rate).and_then(|d| self.apply(d))
+    }
+}
+
+//one line of a chart, import stores its values under key and the worker and the static site
+//draw it with the label as legend and table heading
+#[derive(Debug)]
+pub struct Series {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub label_fr: &'static str,
+    pub rate: Rate,
+    //the css custom property the line is drawn in, it differs between the light and dark theme
+    pub colour: &'static str,
+    //dashes as chart.js takes them, lines differ in more than colour for readers who can't tell them apart
+    pub dash: &'static [u32],
+    pub transform: Transform,
+}
+
+impl Series {
+    pub fn by_key(key: &str) -> Option<&'static Series> {
+        SERIES.iter().find(|s| s.key == key)
+    }
+
+    pub fn outcome(&self) -> Outcome {
+        self.rate.outcome
+    }
+
+    pub fn value(&self, r: &DayReport) -> Option<f64> {
+        (self.rate.per100k)(r).and_then(|rate| self.transform.apply(rate))
+    }
+}
+
+//every chart series, grouped by outcome in the order of Outcome::ALL
+//a new series here is stored by import, kept in the rates table and drawn by the worker and the static site
+pub const SERIES: [Series; 9] = [
+    Series {
+        key: "cases_dose0",
+        label: "0 doses",
+        label_fr: "0 dose",
+        rate: Rate {
+            outcome: Outcome::Cases,
+            doses: Doses::Zero,
+            per100k: |r| r.cases.cases_unvac_rate_per100k,
+        },
+        colour: "--dose0",
+        dash: &[],
+        transform: Transform::DecimalPlaces(2),
+    },
+    Series {
+        key: "cases_dose2",
+        label: "2 doses",
+        label_fr: "2 doses",
+        rate: Rate {
+            outcome: Outcome::Cases,
+            doses: Doses::Two,
+            per100k: |r| Some(r.cases.cases_full_vac_rate_per100k),
+        },
+        colour: "--dose2",
+        dash: &[8, 4],
+        transform: Transform::DecimalPlaces(2),
+    },
+    Series {
+        key: "cases_dose_lt2",
+        label: "less than 2 doses",
+        label_fr: "moins de 2 doses",
+        rate: Rate {
+            outcome: Outcome::Cases,
+            doses: Doses::LessThanTwo,
+            per100k: |r| r.cases.cases_notfull_vac_rate_per100k,
+        },
+        colour: "--dose-lt2",
+        dash: &[2, 2],
+        transform: Transform::DecimalPlaces(2),
+    },
+    Series {
+        key: "nonicu_dose0",
+        label: "0 doses",
+        label_fr: "0 dose",
+        rate: Rate {
+            outcome: Outcome::NonIcu,
+            doses: Doses::Zero,
+            per100k: |r| r.nonicu_unvac_rate_per100k(),
+        },
+        colour: "--dose0",
+        dash: &[],
+        transform: Transform::DecimalPlaces(2),
+    },
+    Series {
+        key: "nonicu_dose2",
+        label: "2 doses",
+        label_fr: "2 doses",
+        rate: Rate {
+            outcome: Outcome::NonIcu,
+            doses: Doses::Two,
+            per100k: |r| Some(r.nonicu_full_vac_rate_per100k()),
+        },
+        colour: "--dose2",
+        dash: &[8, 4],
+        transform: Transform::DecimalPlaces(2),
+    },
+    Series {
+        key: "nonicu_dose_lt2",
+        label: "less than 2 doses",
+        label_fr: "moins de 2 doses",
+        rate: Rate {
+            outcome: Outcome::NonIcu,
+            doses: Doses::LessThanTwo,
+            per100k: |r| r.nonicu_notfull_vac_rate_per100k(),
+        },
+        colour: "--dose-lt2",
+        dash: &[2, 2],
+        transform: Transform::DecimalPlaces(2),
+    },
+    Series {
+        key: "icu_dose0",
+        label: "0 doses",
+        label_fr: "0 dose",
+        rate: Rate {
+            outcome: Outcome::Icu,
+            doses: Doses::Zero,
+            per100k: |r| r.icu_unvac_rate_per100k(),
+        },
+        colour: "--dose0",
+        dash: &[],
+        transform: Transform::SignificantFigures(3),
+    },
+    Series {
+        key: "icu_dose2",
+        label: "2 doses",
+        label_fr: "2 doses",
+        rate: Rate {
+            outcome: Outcome::Icu,
+            doses: Doses::Two,
+            per100k: |r| Some(r.icu_full_vac_rate_per100k()),
+        },
+        colour: "--dose2",
+        dash: &[8, 4],
+        transform: Transform::SignificantFigures(3),
+    },
+    Series {
+        key: "icu_dose_lt2",
+        label: "less than 2 doses",
+        label_fr: "moins de 2 doses",
+        rate: Rate {
+            outcome: Outcome::Icu,
+            doses: Doses::LessThanTwo,
+            per100k: |r| r.icu_notfull_vac_rate_per100k(),
+        },
+        colour: "--dose-lt2",
+        dash: &[2, 2],
+        transform: Transform::SignificantFigures(3),
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn series_registry_is_consistent() {
+        //grouped by outcome in the order of the chart pages
+        let outcomes: Vec<Outcome> = SERIES.iter().map(|s| s.outcome()).collect();
+        let mut sorted = outcomes.clone();
+        sorted.sort();
+        assert_eq!(outcomes, sorted);
+        let keys: HashSet<&str> = SERIES.iter().map(|s| s.key).collect();
+        assert_eq!(keys.len(), SERIES.len());
+        for s in &SERIES {
+            assert!(s.key.starts_with(s.outcome().name()), "{}", s.key);
+            assert_eq!(Series::by_key(s.key).map(|f| f.key), Some(s.key));
+        }
+        for o in Outcome::ALL {
+            assert_eq!(Outcome::by_slug(o.slug()), Some(o));
+            assert!(o.series().count() > 0);
+        }
+        assert_eq!(Outcome::Icu.path(), "/ch/ii/");
+        //one rate per outcome and status in the rates table
+        let rates: HashSet<(&str, &str)> = rates()
+            .map(|r| (r.outcome.name(), r.doses.status()))
+            .collect();
+        assert_eq!(rates.len(), SERIES.len() + UNCHARTED_RATES.len());
+
+        let mut r = DayReport::default();
+        r.cases.cases_unvac_rate_per100k = Some(Decimal::new(12345, 3));
+        assert_eq!(SERIES[0].value(&r), Some(12.35));
+        assert_eq!(SERIES[2].value(&r), None);
+        assert_eq!(Transform::DecimalPlaces(1).apply_f64(0.25), Some(0.3));
+    }
+
+    #[test]
+    fn significant_figures_keep_small_rates() {
+        let sf = Transform::SignificantFigures(3);
+        assert_eq!(sf.apply(Decimal::new(123456, 7)), Some(0.0123));
+        assert_eq!(
+            Transform::DecimalPlaces(2).apply(Decimal::new(123456, 7)),
+            Some(0.01)
+        );
+        assert_eq!(sf.apply(Decimal::new(123456, 3)), Some(123.0));
+        assert_eq!(sf.apply(Decimal::new(-5, 3)), Some(-0.005));
+        assert_eq!(sf.apply(Decimal::ZERO), Some(0.0));
+        assert_eq!(sf.apply_f64(7.7849), Some(7.78));
+        //nothing to round to, a gap rather than a zero
+        assert_eq!(Transform::SignificantFigures(0).apply(Decimal::ONE), None);
+        assert_eq!(sf.apply_f64(f64::NAN), None);
+        assert_eq!(sf.places(0.0523), 4);
+        assert_eq!(sf.places(7.78), 2);
+        assert_eq!(sf.places(1234.0), 0);
+        assert_eq!(Transform::DecimalPlaces(2).places(1234.0), 2);
+        assert!(SERIES
+            .iter()
+            .filter(|s| s.outcome() == Outcome::Icu)
+            .all(|s| s.transform == sf));
+    }
+}
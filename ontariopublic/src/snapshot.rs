@@ -0,0 +1,169 @@
+use crate::{
+    decode, ChartSeries, DataError, DayReport, Encoding, Index, Outcome, Province, Result,
+};
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+
+//the key holding the version of the latest snapshot, small so reading it every minute is cheap,
+//import leaves it empty when it ran without a snapshot
+pub const SNAPSHOT_POINTER: &str = "snapshot";
+
+//everything the provincial pages read in one value, the worker loads it once per isolate
+//and keeps it until the pointer names another version
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub index: Index,
+    //sorted by day like the index
+    reports: Vec<DayReport>,
+    //by Outcome::name
+    charts: BTreeMap<String, ChartSeries>,
+    //the reports in the compact encoding, kept so the worker does not encode one per request
+    #[serde(skip)]
+    encoded: Vec<Vec<u8>>,
+}
+
+impl Snapshot {
+    pub fn new(
+        index: Index,
+        mut reports: Vec<DayReport>,
+        charts: BTreeMap<String, ChartSeries>,
+    ) -> Result<Self> {
+        reports.sort_unstable_by_key(|r| r.cases.date);
+        Snapshot {
+            index,
+            reports,
+            charts,
+            encoded: vec![],
+        }
+        .encode_reports()
+    }
+
+    fn encode_reports(mut self) -> Result<Self> {
+        self.encoded = self
+            .reports
+            .iter()
+            .map(|r| Encoding::Cbor.encode(r))
+            .collect::<Result<_>>()?;
+        Ok(self)
+    }
+
+    //changes with every import, a snapshot is never overwritten so the pointer
+    //can't name a version whose value is not there yet
+    pub fn version(&self) -> String {
+        self.index.updated.format("%Y%m%dT%H%M%S%3f").to_string()
+    }
+
+    //where the snapshot of version is stored
+    pub fn key(province: &Province, version: &str) -> String {
+        province.key(&format!("{}/{}", SNAPSHOT_POINTER, version))
+    }
+
+    pub fn report(&self, day: NaiveDate) -> Option<&DayReport> {
+        self.position(day).map(|i| &self.reports[i])
+    }
+
+    //the report of day in the compact encoding
+    pub fn report_raw(&self, day: NaiveDate) -> Option<&[u8]> {
+        self.position(day).map(|i| self.encoded[i].as_slice())
+    }
+
+    fn position(&self, day: NaiveDate) -> Option<usize> {
+        self.reports
+            .binary_search_by_key(&day, |r| r.cases.date)
+            .ok()
+    }
+
+    pub fn chart(
+        &self,
+        outcome: Outcome,
+        first: Option<NaiveDate>,
+        last: Option<NaiveDate>,
+    ) -> Option<ChartSeries> {
+        self.charts
+            .get(outcome.name())
+            .map(|series| series.clone().within(first, last))
+    }
+
+    //the compact encoding gzipped, a few hundred days fit in well under the kv value limit
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+        gz.write_all(&Encoding::Cbor.encode(self)?)
+            .and_then(|_| gz.finish())
+            .map_err(|e| DataError::Encoding(e.to_string()))
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Snapshot> {
+        let mut raw = Vec::new();
+        GzDecoder::new(bytes)
+            .read_to_end(&mut raw)
+            .map_err(|e| DataError::Encoding(e.to_string()))?;
+        decode::<Snapshot>(&raw)?.encode_reports()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PROVINCES;
+
+    fn report(day: u32) -> DayReport {
+        let mut r = DayReport::default();
+        r.cases.date = NaiveDate::from_ymd(2022, 1, day);
+        r.cases.covid19_cases_unvac = Some(day as i64 * 10);
+        r
+    }
+
+    #[test]
+    fn snapshot_reads_back() {
+        let index = Index::from(&["20220101", "20220102", "20220103"]);
+        let charts: BTreeMap<String, ChartSeries> = [(
+            "cases".to_string(),
+            ChartSeries {
+                labels: vec![
+                    "2022-01-01".into(),
+                    "2022-01-02".into(),
+                    "2022-01-03".into(),
+                ],
+                lines: vec![vec![Some(1.0), None, Some(3.5)], vec![Some(0.5); 3]],
+            },
+        )]
+        .into_iter()
+        .collect();
+        let snapshot = Snapshot::new(index, vec![report(3), report(1), report(2)], charts).unwrap();
+        let bytes = snapshot.to_bytes().unwrap();
+        let read = Snapshot::from_bytes(&bytes).unwrap();
+        assert_eq!(read.version(), snapshot.version());
+        assert_eq!(read.index.keys(), snapshot.index.keys());
+        assert_eq!(
+            read.report(NaiveDate::from_ymd(2022, 1, 2))
+                .unwrap()
+                .cases
+                .covid19_cases_unvac,
+            Some(20)
+        );
+        assert!(read.report(NaiveDate::from_ymd(2022, 1, 4)).is_none());
+        let raw = read.report_raw(NaiveDate::from_ymd(2022, 1, 3)).unwrap();
+        assert_eq!(
+            decode::<DayReport>(raw).unwrap().cases.covid19_cases_unvac,
+            Some(30)
+        );
+        let chart = read
+            .chart(Outcome::Cases, Some(NaiveDate::from_ymd(2022, 1, 2)), None)
+            .unwrap();
+        assert_eq!(chart.labels, ["2022-01-02", "2022-01-03"]);
+        assert_eq!(chart.lines[0], [None, Some(3.5)]);
+        assert!(read.chart(Outcome::Icu, None, None).is_none());
+        assert!(Snapshot::from_bytes(b"not gzip").is_err());
+
+        let other = PROVINCES.iter().find(|p| !p.is_ontario()).unwrap();
+        assert_eq!(
+            Snapshot::key(other, "1"),
+            format!("p/{}/snapshot/1", other.code)
+        );
+    }
+}
@@ -0,0 +1,369 @@
+use crate::{GapCause, Index, Outcome, Province, Result, Series, SERIES};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+//the tables import writes with --output sqlite, the same file is loaded into D1 for the worker
+//a date is yyyy-mm-dd text so it sorts and compares in sql
+pub const SQL_SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS import_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    province TEXT NOT NULL,
+    updated TEXT NOT NULL,
+    days INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS reports (
+    province TEXT NOT NULL,
+    date TEXT NOT NULL,
+    report TEXT NOT NULL,
+    import_run INTEGER NOT NULL REFERENCES import_runs(id),
+    PRIMARY KEY (province, date)
+);
+CREATE TABLE IF NOT EXISTS cases (
+    province TEXT NOT NULL,
+    date TEXT NOT NULL,
+    unvac INTEGER,
+    partial_vac INTEGER,
+    notfull_vac INTEGER,
+    full_vac INTEGER NOT NULL,
+    boost_vac INTEGER,
+    vac_unknown INTEGER,
+    import_run INTEGER NOT NULL REFERENCES import_runs(id),
+    PRIMARY KEY (province, date)
+);
+CREATE INDEX IF NOT EXISTS cases_date ON cases(date);
+CREATE TABLE IF NOT EXISTS hospitalizations (
+    province TEXT NOT NULL,
+    date TEXT NOT NULL,
+    icu_unvac INTEGER NOT NULL,
+    icu_partial_vac INTEGER NOT NULL,
+    icu_full_vac INTEGER NOT NULL,
+    nonicu_unvac INTEGER NOT NULL,
+    nonicu_partial_vac INTEGER NOT NULL,
+    nonicu_full_vac INTEGER NOT NULL,
+    import_run INTEGER NOT NULL REFERENCES import_runs(id),
+    PRIMARY KEY (province, date)
+);
+CREATE INDEX IF NOT EXISTS hospitalizations_date ON hospitalizations(date);
+CREATE TABLE IF NOT EXISTS rates (
+    province TEXT NOT NULL,
+    date TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    status TEXT NOT NULL,
+    per100k REAL NOT NULL,
+    import_run INTEGER NOT NULL REFERENCES import_runs(id),
+    PRIMARY KEY (province, date, outcome, status)
+);
+CREATE INDEX IF NOT EXISTS rates_date ON rates(date);
+CREATE TABLE IF NOT EXISTS skipped (
+    province TEXT NOT NULL,
+    date TEXT NOT NULL,
+    cause TEXT NOT NULL,
+    import_run INTEGER NOT NULL REFERENCES import_runs(id),
+    PRIMARY KEY (province, date)
+);
+";
+
+//?1 province code, rows are DateRow
+pub const INDEX_QUERY: &str = "SELECT date FROM reports WHERE province = ?1 ORDER BY date";
+
+//?1 province code, rows are SkippedRow
+pub const SKIPPED_QUERY: &str = "SELECT date, cause FROM skipped WHERE province = ?1 ORDER BY date";
+
+//?1 province code, a row of UpdatedRow
+pub const UPDATED_QUERY: &str =
+    "SELECT max(updated) AS updated FROM import_runs WHERE province = ?1";
+
+//?1 province code ?2 date, a row of ReportRow
+pub const REPORT_QUERY: &str = "SELECT report FROM reports WHERE province = ?1 AND date = ?2";
+
+//?1 province code ?2 outcome (Outcome::name) ?3 first and ?4 last date, rows are ChartRow
+//every day with a report has a row per status with a rate, or a single row without one
+pub const CHART_QUERY: &str = "
+SELECT r.date AS date, t.status AS status, t.per100k AS per100k
+FROM reports r
+LEFT JOIN rates t
+    ON t.province = r.province AND t.date = r.date AND t.outcome = ?2
+WHERE r.province = ?1 AND r.date BETWEEN ?3 AND ?4
+ORDER BY r.date, t.status";
+
+#[derive(Deserialize, Debug)]
+pub struct DateRow {
+    pub date: NaiveDate,
+}
+
+//cause is GapCause::code
+#[derive(Deserialize, Debug)]
+pub struct SkippedRow {
+    pub date: NaiveDate,
+    pub cause: GapCause,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UpdatedRow {
+    pub updated: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ReportRow {
+    pub report: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct ChartRow {
+    pub date: NaiveDate,
+    pub status: Option<String>,
+    pub per100k: Option<f64>,
+}
+
+//the same index import stores in kv, none when the province was never imported
+pub fn index_from_rows(
+    province: &Province,
+    rows: &[DateRow],
+    skipped: &[SkippedRow],
+    updated: &UpdatedRow,
+) -> Result<Option<Index>> {
+    let updated = match &updated.updated {
+        Some(updated) => DateTime::parse_from_rfc3339(updated)?.with_timezone(&Utc),
+        None => return Ok(None),
+    };
+    let mut index = Index::new(rows.iter().map(|r| r.date))
+        .with_province(province)
+        .with_skipped(skipped.iter().map(|s| (s.date, s.cause)));
+    index.updated = updated;
+    Ok(Some(index))
+}
+
+//the labels and lines of a chart page as import stores them in kv
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChartSeries {
+    pub labels: Vec<String>,
+    //one per series of the outcome in the order of SERIES
+    pub lines: Vec<Vec<Option<f64>>>,
+}
+
+impl ChartSeries {
+    //the rows of CHART_QUERY, statuses without a series are left out
+    //import stores the charted rates rounded by their series so they are taken as they are
+    pub fn from_rows(outcome: Outcome, rows: Vec<ChartRow>) -> ChartSeries {
+        let series: Vec<&Series> = outcome.series().collect();
+        let mut chart = ChartSeries {
+            labels: vec![],
+            lines: vec![vec![]; series.len()],
+        };
+        let mut last = None;
+        for r in rows {
+            if last != Some(r.date) {
+                last = Some(r.date);
+                chart.labels.push(r.date.format("%Y-%m-%d").to_string());
+                chart.lines.iter_mut().for_each(|l| l.push(None));
+            }
+            let (status, per100k) = match (r.status, r.per100k) {
+                (Some(status), Some(per100k)) => (status, per100k),
+                _ => continue,
+            };
+            for (s, line) in series.iter().zip(chart.lines.iter_mut()) {
+                if s.rate.doses.status() == status {
+                    if let Some(v) = line.last_mut() {
+                        *v = Some(per100k);
+                    }
+                }
+            }
+        }
+        chart
+    }
+
+    //the chart of outcome out of a line for every series of SERIES in its order
+    pub fn from_lines(outcome: Outcome, labels: &[String], lines: &[Vec<Option<f64>>]) -> Self {
+        ChartSeries {
+            labels: labels.to_vec(),
+            lines: SERIES
+                .iter()
+                .zip(lines)
+                .filter(|(s, _)| s.outcome() == outcome)
+                .map(|(_, line)| line.clone())
+                .collect(),
+        }
+    }
+
+    //the days from first to last inclusive, what the BETWEEN of CHART_QUERY does for kv values
+    //map_or rather than is_none_or, that needs rust 1.82 and the worker builds with an older one
+    #[allow(clippy::unnecessary_map_or)]
+    pub fn within(self, first: Option<NaiveDate>, last: Option<NaiveDate>) -> ChartSeries {
+        let keep: Vec<bool> = self
+            .labels
+            .iter()
+            .map(|l| match NaiveDate::parse_from_str(l, "%Y-%m-%d") {
+                Ok(d) => first.map_or(true, |f| d >= f) && last.map_or(true, |t| d <= t),
+                Err(_) => false,
+            })
+            .collect();
+        ChartSeries {
+            labels: kept(self.labels, &keep),
+            lines: self.lines.into_iter().map(|l| kept(l, &keep)).collect(),
+        }
+    }
+
+    //every day from the first label to the last, a day without a report gets the straight line
+    //between the days around it, a line without a value on either side stays empty
+    pub fn interpolated(self, outcome: Outcome) -> ChartSeries {
+        let days: Vec<Option<NaiveDate>> = self
+            .labels
+            .iter()
+            .map(|l| NaiveDate::parse_from_str(l, "%Y-%m-%d").ok())
+            .collect();
+        let series: Vec<&Series> = outcome.series().collect();
+        let mut chart = ChartSeries {
+            labels: vec![],
+            lines: vec![vec![]; self.lines.len()],
+        };
+        for (i, label) in self.labels.iter().enumerate() {
+            chart.labels.push(label.clone());
+            for (line, from) in chart.lines.iter_mut().zip(&self.lines) {
+                line.push(from.get(i).copied().flatten());
+            }
+            let (day, next) = match (days[i], days.get(i + 1).copied().flatten()) {
+                (Some(day), Some(next)) => (day, next),
+                _ => continue,
+            };
+            let span = (next - day).num_days();
+            for k in 1..span {
+                let t = k as f64 / span as f64;
+                chart
+                    .labels
+                    .push((day + Duration::days(k)).format("%Y-%m-%d").to_string());
+                for (n, (line, from)) in chart.lines.iter_mut().zip(&self.lines).enumerate() {
+                    let at = |i: usize| from.get(i).copied().flatten();
+                    let v = between(at(i), at(i + 1), t);
+                    //rounded like the stored values of the series
+                    line.push(match series.get(n) {
+                        Some(s) => v.and_then(|v| s.transform.apply_f64(v)),
+                        None => v,
+                    });
+                }
+            }
+        }
+        chart
+    }
+}
+
+//t of the way from a to b
+fn between(a: Option<f64>, b: Option<f64>, t: f64) -> Option<f64> {
+    Some(a? + (b? - a?) * t)
+}
+
+fn kept<T>(values: Vec<T>, keep: &[bool]) -> Vec<T> {
+    values
+        .into_iter()
+        .zip(keep)
+        .filter(|(_, k)| **k)
+        .map(|(v, _)| v)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ONTARIO;
+
+    //the rows CHART_QUERY gives for a day, 0 doses at dose0 and 2 doses at 1.5
+    fn rows(day: u32, dose0: Option<f64>) -> Vec<ChartRow> {
+        let date = NaiveDate::from_ymd(2022, 1, day);
+        let row = |status: &str, per100k| ChartRow {
+            date,
+            status: Some(status.into()),
+            per100k,
+        };
+        vec![row("full_vac", Some(1.5)), row("unvac", dose0)]
+    }
+
+    fn chart(days: &[(u32, Option<f64>)]) -> ChartSeries {
+        let rows = days.iter().flat_map(|&(day, dose0)| rows(day, dose0));
+        ChartSeries::from_rows(Outcome::Cases, rows.collect())
+    }
+
+    #[test]
+    fn series_from_rows_within_range() {
+        let series = chart(&[(1, Some(10.0)), (2, None), (3, Some(30.25))]);
+        assert_eq!(series.labels, ["2022-01-01", "2022-01-02", "2022-01-03"]);
+        assert_eq!(series.lines.len(), Outcome::Cases.series().count());
+        assert_eq!(series.lines[0], [Some(10.0), None, Some(30.25)]);
+        let within = series.within(Some(NaiveDate::from_ymd(2022, 1, 2)), None);
+        assert_eq!(within.labels, ["2022-01-02", "2022-01-03"]);
+        assert_eq!(within.lines[0], [None, Some(30.25)]);
+        assert_eq!(within.lines[1], [Some(1.5), Some(1.5)]);
+        assert_eq!(within.lines[2], [None, None]);
+        //a day without rates still has a label
+        let bare = ChartRow {
+            date: NaiveDate::from_ymd(2022, 1, 4),
+            status: None,
+            per100k: None,
+        };
+        let series = ChartSeries::from_rows(Outcome::Icu, vec![bare]);
+        assert_eq!(series.labels, ["2022-01-04"]);
+        assert_eq!(series.lines, [[None], [None], [None]]);
+        let updated = UpdatedRow {
+            updated: Some("2022-07-08T00:00:00+00:00".into()),
+        };
+        let dates = [
+            DateRow {
+                date: NaiveDate::from_ymd(2022, 1, 2),
+            },
+            DateRow {
+                date: NaiveDate::from_ymd(2022, 1, 5),
+            },
+        ];
+        let skipped = [SkippedRow {
+            date: NaiveDate::from_ymd(2022, 1, 3),
+            cause: GapCause::FailedValidation,
+        }];
+        let index = index_from_rows(ONTARIO, &dates, &skipped, &updated)
+            .unwrap()
+            .unwrap();
+        assert_eq!(index.keys(), ["20220102", "20220105"]);
+        assert_eq!(index.updated.to_rfc3339(), "2022-07-08T00:00:00+00:00");
+        assert_eq!(
+            index.cause(NaiveDate::from_ymd(2022, 1, 3)),
+            Some(GapCause::FailedValidation)
+        );
+        assert_eq!(
+            index.cause(NaiveDate::from_ymd(2022, 1, 4)),
+            Some(GapCause::MissingUpstream)
+        );
+        let never = UpdatedRow { updated: None };
+        assert!(index_from_rows(ONTARIO, &[], &[], &never)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn interpolated_fills_missing_days() {
+        let series = chart(&[(1, Some(10.0)), (4, Some(40.0)), (5, None)]);
+        let filled = series.interpolated(Outcome::Cases);
+        assert_eq!(
+            filled.labels,
+            [
+                "2022-01-01",
+                "2022-01-02",
+                "2022-01-03",
+                "2022-01-04",
+                "2022-01-05"
+            ]
+        );
+        assert_eq!(
+            filled.lines[0],
+            [Some(10.0), Some(20.0), Some(30.0), Some(40.0), None]
+        );
+        assert_eq!(filled.lines[1], [Some(1.5); 5]);
+        assert_eq!(filled.lines[2], [None; 5]);
+        let none = chart(&[(1, Some(1.0)), (4, None)]);
+        assert_eq!(
+            none.interpolated(Outcome::Cases).lines[0],
+            [Some(1.0), None, None, None]
+        );
+        let thirds = chart(&[(1, Some(0.0)), (4, Some(1.0))]);
+        assert_eq!(
+            thirds.interpolated(Outcome::Cases).lines[0],
+            [Some(0.0), Some(0.33), Some(0.67), Some(1.0)]
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ontariopublic::{CasesByVacStatus, DayReport, HospitalizationByVacStatus, Index};
+use rust_decimal::Decimal;
+use vaxrender::{render_detail_report_str, render_report_str, Host, Lang};
+
+pub fn render_benchmark(c: &mut Criterion) {
+    let idx = Index::from(&["20220101", "20220102"]);
+    let cases = CasesByVacStatus {
+        id: 33,
+        date: NaiveDate::from_ymd(2022, 1, 1),
+        covid19_cases_unvac: Some(4),
+        covid19_cases_partial_vac: Some(2),
+        covid19_cases_full_vac: 1,
+        covid19_cases_vac_unknown: Some(0),
+        cases_unvac_rate_per100k: Some(Decimal::new(4, 0)),
+        cases_partial_vac_rate_per100k: Some(Decimal::new(2, 0)),
+        cases_full_vac_rate_per100k: Decimal::new(1, 0),
+        cases_unvac_rate_7ma: Some(Decimal::new(4, 0)),
+        cases_partial_vac_rate_7ma: Some(Decimal::new(2, 0)),
+        cases_full_vac_rate_7ma: Some(Decimal::new(1, 0)),
+        ..Default::default()
+    };
+    let hosps = HospitalizationByVacStatus::default();
+    let report = DayReport::from(cases, hosps);
+
+    c.bench_function("render_report_str", |b| {
+        b.iter(|| {
+            render_report_str(black_box(&idx), black_box(&report), Lang::En, Host::Worker).unwrap()
+        })
+    });
+    c.bench_function("render_detail_report_str", |b| {
+        b.iter(|| render_detail_report_str(black_box(&idx), black_box(&report), Lang::En).unwrap())
+    });
+}
+
+criterion_group!(benches, render_benchmark);
+criterion_main!(benches);
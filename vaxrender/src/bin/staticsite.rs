@@ -0,0 +1,275 @@
+//renders the whole site from the bulk.json written by import into a directory
+//that any static file server can host, pages are written as <path>/index.html
+use anyhow::{anyhow, Context, Result};
+use clap::{Arg, Command};
+use ontariopublic::{
+    day_key, decode, ChartSeries, DayReport, Index, Outcome, Province, RegionReport, PHUS,
+    PROVINCES, SERIES,
+};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+use vaxrender::{
+    render_calendar_str, render_chart_str, render_compare_str, render_detail_report_str,
+    render_error_page_str, render_feed_str, render_region_str, render_report_str, ErrorPage,
+    Headline, Host, Lang, CSS,
+};
+
+#[derive(Deserialize)]
+struct Entry {
+    key: String,
+    value: String,
+    #[serde(default)]
+    base64: bool,
+}
+
+//key value pairs as they would be stored in the worker's KV namespace
+struct Bulk(HashMap<String, Vec<u8>>);
+
+impl Bulk {
+    fn read(fname: &str) -> Result<Bulk> {
+        let f = File::open(fname).with_context(|| format!("Failed to read from {}", fname))?;
+        let entries: Vec<Entry> = serde_json::from_reader(BufReader::new(f))
+            .with_context(|| format!("Failed to deserialize {}", fname))?;
+        let mut values = HashMap::new();
+        for e in entries {
+            //wrangler stores these decoded, so do we
+            let value = if e.base64 {
+                base64::decode(&e.value)
+                    .with_context(|| format!("Failed to decode {} in {}", e.key, fname))?
+            } else {
+                e.value.into_bytes()
+            };
+            values.insert(e.key, value);
+        }
+        Ok(Bulk(values))
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> Result<T> {
+        let value = self
+            .0
+            .get(key)
+            .ok_or_else(|| anyhow!("No such value in bulk file -> {}", key))?;
+        decode(value).with_context(|| format!("Failed to deserialize {}", key))
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.0.contains_key(key)
+    }
+}
+
+struct Site {
+    out: PathBuf,
+    //pages written so far, relative to the site root, with the last update of their
+    //province as %Y-%m-%d for the sitemap
+    pages: Vec<(String, String)>,
+}
+
+impl Site {
+    //url paths end with / and become a directory with an index.html
+    fn page(&mut self, path: &str, body: &str, index: &Index) -> Result<()> {
+        let dir = self.out.join(path.trim_start_matches('/'));
+        self.file(&dir.join("index.html"), body)?;
+        let lastmod = index.updated.format("%Y-%m-%d").to_string();
+        self.pages.push((path.to_string(), lastmod));
+        Ok(())
+    }
+
+    fn file(&self, fname: &Path, body: &str) -> Result<()> {
+        if let Some(dir) = fname.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(fname, body).with_context(|| format!("Failed to write {}", fname.display()))
+    }
+}
+
+fn main() -> Result<()> {
+    let matches = Command::new("VaxStaticSite")
+        .version("0.1")
+        .author("Jakub Labath. <jakub@labath.ca>")
+        .about("Pre-renders every page of the site into a directory")
+        .arg(
+            Arg::new("bulk")
+                .long("bulk")
+                .help("Bulk file written by import")
+                .takes_value(true)
+                .default_value("../import/bulk.json"),
+        )
+        .arg(
+            Arg::new("out")
+                .long("out")
+                .help("Directory to write the site into")
+                .takes_value(true)
+                .default_value("site"),
+        )
+        .arg(
+            Arg::new("base url")
+                .long("base-url")
+                .help("Where the site will be hosted, used for the sitemap and the feeds")
+                .takes_value(true)
+                .default_value("https://vax.labath.ca"),
+        )
+        .get_matches();
+
+    let bulk = Bulk::read(matches.value_of("bulk").unwrap_or_default())?;
+    let base = matches
+        .value_of("base url")
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    let mut site = Site {
+        out: PathBuf::from(matches.value_of("out").unwrap_or_default()),
+        pages: vec![],
+    };
+
+    //one deployment can publish several provinces, each with its own index
+    let mut indexes = vec![];
+    for province in PROVINCES.iter().filter(|p| bulk.contains(&p.key("index"))) {
+        indexes.push(province_pages(&mut site, &bulk, province, base)?);
+    }
+    let index = indexes
+        .first()
+        .ok_or_else(|| anyhow!("No index of any province in the bulk file"))?;
+    //static hosts commonly serve 404.html for missing paths
+    let nearest = index.last().map(day_key);
+    site.file(
+        &site.out.join("404.html"),
+        &render_error_page_str(
+            ErrorPage::NotFound,
+            nearest.as_deref(),
+            index.province(),
+            "/",
+            Lang::En,
+        )?,
+    )?;
+    site.file(&site.out.join("style.css"), CSS)?;
+    write_sitemap(&site, base)?;
+    println!("Wrote {} pages to {}", site.pages.len(), site.out.display());
+    Ok(())
+}
+
+//every page of a province under its prefix, the public health units are ontario's
+fn province_pages(site: &mut Site, bulk: &Bulk, province: &Province, base: &str) -> Result<Index> {
+    let index: Index = bulk.get(&province.key("index"))?;
+    let mut reports = BTreeMap::new();
+    for key in index.keys() {
+        let report: DayReport = bulk.get(&province.key(&key))?;
+        reports.insert(key, report);
+    }
+    let latest_key = index
+        .last()
+        .map(day_key)
+        .ok_or_else(|| anyhow!("The index of {} is empty", province.code))?;
+    let latest = &reports[&latest_key];
+    let labels: Vec<String> = bulk.get(&province.key("labels"))?;
+    let mut series: Vec<Vec<Option<f64>>> = Vec::with_capacity(SERIES.len());
+    for s in &SERIES {
+        series.push(bulk.get(&province.key(s.key))?);
+    }
+    let headlines = Headline::from_series(&labels, &series);
+    //public health units with data, import leaves out the ones it had nothing for
+    let mut regions = vec![];
+    let mut phus = vec![];
+    for phu in PHUS
+        .iter()
+        .filter(|phu| province.is_ontario() && bulk.contains(&phu.key("index")))
+    {
+        let index: Index = bulk.get(&phu.key("index"))?;
+        let mut days = BTreeMap::new();
+        for key in index.keys() {
+            let report: RegionReport = bulk.get(&phu.key(&key))?;
+            days.insert(key, report);
+        }
+        //a unit without any day has no page
+        if let Some(last) = index.last().map(day_key) {
+            regions.push((phu, index, days, last));
+            phus.push(phu);
+        }
+    }
+    //no worker answers the forms, the region selector becomes links to the pages written here
+    let host = Host::Static(&phus);
+
+    for lang in [Lang::En, Lang::Fr] {
+        let p = format!("{}{}", lang.prefix(), province.prefix());
+        site.page(
+            &format!("{}/", p),
+            &render_report_str(&index, latest, lang, host)?,
+            &index,
+        )?;
+        for (key, report) in &reports {
+            site.page(
+                &format!("{}/d/{}/", p, key),
+                &render_report_str(&index, report, lang, host)?,
+                &index,
+            )?;
+            site.page(
+                &format!("{}/dd/{}/", p, key),
+                &render_detail_report_str(&index, report, lang)?,
+                &index,
+            )?;
+            //the report and detail pages of a day only link to its comparison with the most
+            //recent day, without the compare form no other pair is reachable
+            site.page(
+                &format!("{}/cmp/{}/{}/", p, key, latest_key),
+                &render_compare_str(&index, report, latest, lang, host)?,
+                &index,
+            )?;
+        }
+        for (phu, region_index, days, last) in &regions {
+            site.page(
+                &format!("{}/r/{}/", p, phu.slug),
+                &render_region_str(phu, region_index, &days[last], lang, host)?,
+                region_index,
+            )?;
+            for (key, report) in days {
+                site.page(
+                    &format!("{}/r/{}/d/{}/", p, phu.slug, key),
+                    &render_region_str(phu, region_index, report, lang, host)?,
+                    region_index,
+                )?;
+            }
+        }
+        for outcome in Outcome::ALL {
+            let chart = ChartSeries::from_lines(outcome, &labels, &series);
+            site.page(
+                &format!("{}{}", p, outcome.path()),
+                &render_chart_str(outcome, province, lang, &chart)?,
+                &index,
+            )?;
+        }
+        site.page(
+            &format!("{}/calendar/", p),
+            &render_calendar_str(&index, lang)?,
+            &index,
+        )?;
+        site.file(
+            &site
+                .out
+                .join(format!("{}/feed.atom", p).trim_start_matches('/')),
+            &render_feed_str(&index, &headlines, base, lang)?,
+        )?;
+    }
+    Ok(index)
+}
+
+fn write_sitemap(site: &Site, base: &str) -> Result<()> {
+    let fname = site.out.join("sitemap.xml");
+    let mut f =
+        File::create(&fname).with_context(|| format!("Failed to create {}", fname.display()))?;
+    writeln!(f, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        f,
+        r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">"#
+    )?;
+    for (page, lastmod) in &site.pages {
+        writeln!(
+            f,
+            "  <url><loc>{}{}</loc><lastmod>{}</lastmod></url>",
+            base, page, lastmod
+        )?;
+    }
+    writeln!(f, "</urlset>")?;
+    Ok(())
+}
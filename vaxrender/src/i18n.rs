@@ -0,0 +1,604 @@
+use chrono::{DateTime, Datelike, NaiveDate, Timelike, Utc};
+use num_format::{Locale, ToFormattedString};
+use ontariopublic::{Province, Series};
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+//languages the site is published in - Ontario publishes its data in both
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Fr,
+}
+
+impl Lang {
+    //the language is picked by the /fr/ path prefix
+    pub fn from_path(path: &str) -> Self {
+        if path == "/fr" || path.starts_with("/fr/") {
+            Lang::Fr
+        } else {
+            Lang::En
+        }
+    }
+
+    //picks the first supported language in an Accept-Language header honouring the q weights
+    pub fn from_accept_language(header: &str) -> Self {
+        let mut best = (Lang::En, 0.0_f32);
+        for part in header.split(',') {
+            let mut pieces = part.split(';');
+            let tag = pieces.next().unwrap_or("").trim().to_ascii_lowercase();
+            let q = pieces
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let lang = match tag.split('-').next() {
+                Some("fr") => Lang::Fr,
+                Some("en") => Lang::En,
+                _ => continue,
+            };
+            if q > best.1 {
+                best = (lang, q);
+            }
+        }
+        best.0
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Fr => "fr",
+        }
+    }
+
+    //prepended to every link so navigation stays in the same language
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            Lang::En => "",
+            Lang::Fr => "/fr",
+        }
+    }
+
+    pub fn other(&self) -> Lang {
+        match self {
+            Lang::En => Lang::Fr,
+            Lang::Fr => Lang::En,
+        }
+    }
+
+    pub fn messages(&self) -> &'static Messages {
+        match self {
+            Lang::En => &EN,
+            Lang::Fr => &FR,
+        }
+    }
+
+    fn locale(&self) -> &'static Locale {
+        match self {
+            Lang::En => &Locale::en,
+            Lang::Fr => &Locale::fr,
+        }
+    }
+
+    pub fn province_name(&self, province: &Province) -> &'static str {
+        match self {
+            Lang::En => province.name,
+            Lang::Fr => province.name_fr,
+        }
+    }
+
+    //the legend and table heading of a chart series
+    pub fn series_label(&self, series: &Series) -> &'static str {
+        match self {
+            Lang::En => series.label,
+            Lang::Fr => series.label_fr,
+        }
+    }
+
+    //how the headings end e.g. in Ontario, Canada.
+    pub fn in_province(&self, province: &Province) -> String {
+        match self {
+            Lang::En => format!("in {}, Canada.", province.name),
+            Lang::Fr => format!("{}, Canada.", province.in_fr),
+        }
+    }
+
+    //e.g. Sunday, 2 January, 2022 or dimanche 2 janvier 2022
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        let t = self.messages();
+        let weekday = t.weekdays[date.weekday().num_days_from_monday() as usize];
+        let month = t.months[date.month0() as usize];
+        match self {
+            Lang::En => format!("{}, {} {}, {}", weekday, date.day(), month, date.year()),
+            Lang::Fr => format!("{} {} {} {}", weekday, date.day(), month, date.year()),
+        }
+    }
+
+    pub fn format_datetime(&self, dt: &DateTime<Utc>) -> String {
+        match self {
+            Lang::En => dt.to_rfc2822(),
+            Lang::Fr => format!(
+                "{} à {} h {:02} UTC",
+                self.format_date(dt.naive_utc().date()),
+                dt.hour(),
+                dt.minute()
+            ),
+        }
+    }
+
+    //e.g. January 2022 or janvier 2022
+    pub fn format_month(&self, year: i32, month0: u32) -> String {
+        format!("{} {}", self.messages().months[month0 as usize], year)
+    }
+
+    pub fn format_int(&self, n: i64) -> String {
+        n.to_formatted_string(self.locale())
+    }
+
+    //a chart value with a fixed number of places so a column lines up, grouped like format_int
+    pub fn format_float(&self, f: f64, places: u32) -> String {
+        match Decimal::from_f64(f) {
+            Some(d) => {
+                let mut d =
+                    d.round_dp_with_strategy(places, RoundingStrategy::MidpointAwayFromZero);
+                d.rescale(places);
+                self.format_grouped(d)
+            }
+            None => f.to_string(),
+        }
+    }
+
+    //the percent sign is separated by a space in French
+    pub fn format_percent(&self, formatted: String) -> String {
+        match self {
+            Lang::En => format!("{}%", formatted),
+            Lang::Fr => format!("{}\u{a0}%", formatted),
+        }
+    }
+
+    pub fn format_decimal(&self, d: Decimal) -> String {
+        self.format_grouped(d.round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero))
+    }
+
+    //the whole part grouped by the locale, then its decimal separator and the places d has
+    fn format_grouped(&self, d: Decimal) -> String {
+        let locale = self.locale();
+        let abs = d.abs().to_string();
+        let (int, frac) = abs.split_once('.').unwrap_or((&abs, ""));
+        let mut s = String::new();
+        if d.is_sign_negative() && !d.is_zero() {
+            s.push_str(locale.minus_sign());
+        }
+        match int.parse::<u64>() {
+            Ok(n) => s.push_str(&n.to_formatted_string(locale)),
+            Err(_) => s.push_str(int),
+        }
+        if !frac.is_empty() {
+            s.push_str(locale.decimal());
+            s.push_str(frac);
+        }
+        s
+    }
+}
+
+//one row of the detail report, label and explanation of the value
+pub struct DetailLabel {
+    pub label: &'static str,
+    pub desc: &'static str,
+}
+
+const fn dl(label: &'static str, desc: &'static str) -> DetailLabel {
+    DetailLabel { label, desc }
+}
+
+//the message catalogue - every piece of text shown on the site
+pub struct Messages {
+    pub language_name: &'static str,
+    pub weekdays: [&'static str; 7],
+    pub months: [&'static str; 12],
+    pub not_available: &'static str,
+    pub source_code: &'static str,
+    pub discontinued: &'static str,
+    pub updated: &'static str,
+    pub previous: &'static str,
+    pub next: &'static str,
+    pub home: &'static str,
+    pub report_for: &'static str,
+    pub report_heading: &'static str,
+    pub detailed_link: &'static str,
+    pub rate_per_100k: &'static str,
+    pub dose0: &'static str,
+    pub dose_lt2: &'static str,
+    pub dose2: &'static str,
+    pub tested_positive: &'static str,
+    pub hosp_nonicu: &'static str,
+    pub hosp_icu: &'static str,
+    pub detail_heading: &'static str,
+    pub back_to_compare: &'static str,
+    pub cases_heading: &'static str,
+    pub hosps_heading: &'static str,
+    pub hosps_note: &'static str,
+    pub computed_heading: &'static str,
+    pub computed_note: &'static str,
+    pub cases_rows: [DetailLabel; 16],
+    pub hosps_rows: [DetailLabel; 6],
+    pub computed_rows: [DetailLabel; 13],
+    pub standardised_heading: &'static str,
+    pub standardised_rows: [DetailLabel; 18],
+    pub chart_cases_title: &'static str,
+    pub chart_nonicu_title: &'static str,
+    pub chart_icu_title: &'static str,
+    pub skip_to_content: &'static str,
+    pub day_navigation: &'static str,
+    pub compare_caption: &'static str,
+    pub pick_day: &'static str,
+    pub go: &'static str,
+    pub measure: &'static str,
+    pub value: &'static str,
+    pub description: &'static str,
+    pub date: &'static str,
+    pub chart_table: &'static str,
+    pub weekdays_short: [&'static str; 7],
+    pub calendar: &'static str,
+    pub calendar_heading: &'static str,
+    pub calendar_note: &'static str,
+    pub gaps_heading: &'static str,
+    pub gap_missing_upstream: &'static str,
+    pub gap_failed_validation: &'static str,
+    pub gap_unmatched_join: &'static str,
+    pub jump_to_date: &'static str,
+    pub keyboard_help: &'static str,
+    pub compare_heading: &'static str,
+    pub compare_link: &'static str,
+    pub compare_first: &'static str,
+    pub compare_second: &'static str,
+    pub change: &'static str,
+    pub percent_change: &'static str,
+    pub not_found_heading: &'static str,
+    pub not_found_note: &'static str,
+    pub bad_request_heading: &'static str,
+    pub bad_request_note: &'static str,
+    pub unavailable_heading: &'static str,
+    pub unavailable_note: &'static str,
+    pub nearest_day: &'static str,
+    pub coverage_caption: &'static str,
+    pub at_least_one_dose: &'static str,
+    pub fully_vaccinated: &'static str,
+    pub third_dose: &'static str,
+    pub hospitalized_total: &'static str,
+    pub icu_total: &'static str,
+    pub ventilator_total: &'static str,
+    pub region: &'static str,
+    pub province: &'static str,
+    pub region_note: &'static str,
+    pub region_cases_caption: &'static str,
+    pub active_cases: &'static str,
+    pub resolved_cases: &'static str,
+    pub deaths: &'static str,
+}
+
+const DITTO: &str = "\u{3003}";
+//the compare view puts each crude rate next to its age standardised one
+const CRUDE_EN: &str = "The rate over the whole population with this vaccination status, as published or calculated above.";
+const STANDARDISED_EN: &str = "The age specific rates weighted by the 2021 census population of Ontario, so that groups with different age structures can be compared. Only the age groups with a rate for every vaccination status on this day are used.";
+const CRUDE_FR: &str = "Le taux sur l'ensemble de la population ayant ce statut vaccinal, tel que publié ou calculé ci-dessus.";
+const STANDARDISED_FR: &str = "Les taux par groupe d'âge pondérés par la population de l'Ontario au recensement de 2021, afin de comparer des groupes dont la structure par âge diffère. Seuls les groupes d'âge ayant un taux pour chaque statut vaccinal ce jour-là sont utilisés.";
+
+static EN: Messages = Messages {
+    language_name: "English",
+    weekdays: [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ],
+    months: [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ],
+    not_available: "N/A",
+    source_code: "source code",
+    discontinued: "Ontario government: Please note that Cases by Vaccination Status and Hospitalization by Vaccination Status data will no longer be published as of June 23, 2022.",
+    updated: "Updated",
+    previous: "Previous",
+    next: "Next",
+    home: "home",
+    report_for: "Report for",
+    report_heading: "COVID-19 per capita comparison by vaccination status",
+    detailed_link: "Click here for detailed report",
+    rate_per_100k: "Rate per 100,000",
+    dose0: "0 doses",
+    dose_lt2: "< 2 doses",
+    dose2: "2 doses",
+    tested_positive: "Tested positive",
+    hosp_nonicu: "Hospitalized not in ICU",
+    hosp_icu: "Hospitalized in ICU",
+    detail_heading: "Detailed report for",
+    back_to_compare: "Back to compare view",
+    cases_heading: "COVID-19 cases by vaccination status",
+    hosps_heading: "COVID-19 hospitalizations by vaccination status",
+    hosps_note: "Due to incomplete weekend and holiday reporting, vaccination status data for hospital and ICU admissions is not updated on Sundays, Mondays and the day after holidays.",
+    computed_heading: "Computed metrics based on government data above",
+    computed_note: "The per 100,000 scale is used when the more commonly used per cent (per hundred) scale would result in very small decimal numbers. E.g. 1 in 100,000 equals 0.001%, or 1000 in 100,000 equals 1% of the population.",
+    cases_rows: [
+        dl("Unvaccinated", "Number of people who tested positive for COVID-19 on this date. Individuals are considered unvaccinated if they have not had a dose, or if their first dose was less than fourteen days ago."),
+        dl("Partially Vaccinated", "Number of people who tested positive for COVID-19 on this date.  Individuals are considered partially vaccinated if they have had one dose at least fourteen days ago, or two doses where the second dose was less than fourteen days ago."),
+        dl("Not fully vaccinated (less than 2 doses)", "Number of not fully vaccinated people who tested positive for COVID-19 on this date. Individuals are considered fully vaccinated if they have had two doses and the second dose was at least fourteen days ago. New datapoint as of March 11, 2022."),
+        dl("Fully vaccinated", "Number of people who tested positive for COVID-19 on this date. Individuals are considered fully vaccinated if they have had two doses and the second dose was at least fourteen days ago."),
+        dl("Boosted (3 doses)", "Number of boosted people who tested positive for COVID-19 on this date. Individuals are considered fully vaccinated if they have had three doses and the third dose was at least fourteen days ago. New datapoint as of March 11, 2022."),
+        dl("Unknown vaccination status", "Number of people who tested positive for COVID-19 on this date, but their vaccination status is unknown."),
+        dl("Unvaccinated rate per 100,000", "Rate of COVID-19 cases per 100,000 of unvaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000)."),
+        dl("Partially vaccinated rate per 100,000", "Rate of COVID-19 cases per 100,000 of partially vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000)."),
+        dl("Not fully vaccinated rate per 100,000", "Rate of COVID-19 cases per 100,000 of not fully vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000). New as of March 11, 2022."),
+        dl("Fully vaccinated rate per 100,000", "Rate of COVID-19 cases per 100,000 of fully vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000)."),
+        dl("Boosted rate per 100,000", "Rate of COVID-19 cases per 100,000 of fully vaccinated people (calculated by dividing the number of cases for a vaccination status, by the total number of people with the same vaccination status and then multiplying by 100,000). New as of March 11, 2022."),
+        dl("Unvaccinated rate per 100,000 (7 day moving average)", "The average rate of COVID-19 cases per 100,000 for the previous 7 days for this vaccination status."),
+        dl("Partially vaccinated rate per 100,000 (7 day moving average)", DITTO),
+        dl("Not fully vaccinated rate per 100,000 (7 day moving average)", DITTO),
+        dl("Fully vaccinated rate per 100,000 (7 day moving average)", DITTO),
+        dl("Boosted rate per 100,000 (7 day moving average)", DITTO),
+    ],
+    hosps_rows: [
+        dl("Unvaccinated hospitalized but not in ICU", "Number of people admitted to a hospital but not requiring a stay in ICU. In order to understand the vaccination status of patients currently hospitalized, a new data collection process was developed and this may cause discrepancies between other hospitalization numbers being collected using a different data collection process."),
+        dl("Partially vaccinated hospitalized but not in ICU", DITTO),
+        dl("Fully vaccinated hospitalized but not in ICU", DITTO),
+        dl("Unvaccinated in ICU", "Number of people hospitalized in ICU with COVID-19. Data on patients in ICU are being collected from two different data sources with different extraction times and public reporting cycles. The existing data source (Critical Care Information System, CCIS) does not have vaccination status."),
+        dl("Partially vaccinated in ICU", DITTO),
+        dl("Fully vaccinated in ICU", DITTO),
+    ],
+    computed_rows: [
+        dl("Number of unvaccinated people", "Calculated as case count for this vaccination status times 100,000 and then divided by rate and rounded."),
+        dl("Number of partially vaccinated people", DITTO),
+        dl("Number of not fully vaccinated people (less than 2 doses)", DITTO),
+        dl("Number of fully vaccinated people", DITTO),
+        dl("Number of boosted people", DITTO),
+        dl("Non ICU hospitalization rate of unvaccinated per 100,000", "Calculated as number of non ICU hospitalizations for this vaccination status times 100,000 and then divided by the population for this vaccination status."),
+        dl("Non ICU hospitalization rate of partially vaccinated per 100,000", DITTO),
+        dl("Non ICU hospitalization rate of not fully vaccinated per 100,000", DITTO),
+        dl("Non ICU hospitalization rate of fully vaccinated per 100,000", DITTO),
+        dl("ICU hospitalization rate of unvaccinated per 100,000", "Calculated as number of ICU hospitalizations for this vaccination status times 100,000 and then divided by the population for this vaccination status."),
+        dl("ICU hospitalization rate of partially vaccinated per 100,000", DITTO),
+        dl("ICU hospitalization rate of not fully vaccinated per 100,000", DITTO),
+        dl("ICU hospitalization rate of fully vaccinated per 100,000", DITTO),
+    ],
+    standardised_heading: "Crude and age-standardised rates per 100,000",
+    standardised_rows: [
+        dl("Case rate of unvaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("Case rate of unvaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("Case rate of not fully vaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("Case rate of not fully vaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("Case rate of fully vaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("Case rate of fully vaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("Non ICU hospitalization rate of unvaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("Non ICU hospitalization rate of unvaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("Non ICU hospitalization rate of not fully vaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("Non ICU hospitalization rate of not fully vaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("Non ICU hospitalization rate of fully vaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("Non ICU hospitalization rate of fully vaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("ICU hospitalization rate of unvaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("ICU hospitalization rate of unvaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("ICU hospitalization rate of not fully vaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("ICU hospitalization rate of not fully vaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+        dl("ICU hospitalization rate of fully vaccinated per 100,000 (crude)", CRUDE_EN),
+        dl("ICU hospitalization rate of fully vaccinated per 100,000 (age-standardised)", STANDARDISED_EN),
+    ],
+    chart_cases_title: "COVID-19 cases by vaccination status per 100,000 people",
+    chart_nonicu_title: "COVID-19 hospitalizations (not in ICU) by vaccination status per 100,000 people",
+    chart_icu_title: "COVID-19 hospitalization in ICU by vaccination status per 100,000 people",
+    skip_to_content: "Skip to content",
+    day_navigation: "Day navigation",
+    compare_caption: "Rate per 100,000 people by number of vaccine doses",
+    pick_day: "Pick a day",
+    go: "Go",
+    measure: "Measure",
+    value: "Value",
+    description: "Description",
+    date: "Date",
+    chart_table: "Show the chart data as a table",
+    weekdays_short: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"],
+    calendar: "Calendar",
+    calendar_heading: "Days with data",
+    calendar_note: "Linked days have a report. Why the days in between have none is listed below the calendar.",
+    gaps_heading: "Days without a report",
+    gap_missing_upstream: "not published",
+    gap_failed_validation: "did not pass validation",
+    gap_unmatched_join: "hospitalizations and cases were not both published",
+    jump_to_date: "Jump to date",
+    keyboard_help: "Keyboard shortcuts: left arrow for the previous day, right arrow for the next day.",
+    compare_heading: "Comparison of two days",
+    compare_link: "Compare with another day",
+    compare_first: "First day",
+    compare_second: "Second day",
+    change: "Change",
+    percent_change: "% change",
+    not_found_heading: "No report for this day",
+    not_found_note: "There is no report for the day you asked for.",
+    bad_request_heading: "Invalid request",
+    bad_request_note: "The address does not contain a valid day.",
+    unavailable_heading: "Data temporarily unavailable",
+    unavailable_note: "The reports could not be loaded. Please try again in a few minutes.",
+    nearest_day: "The closest day with a report is",
+    coverage_caption: "Vaccine coverage and hospital occupancy in Ontario",
+    at_least_one_dose: "People 5 and older with at least one dose",
+    fully_vaccinated: "People 5 and older fully vaccinated",
+    third_dose: "People 5 and older with a third dose",
+    hospitalized_total: "COVID-19 patients in hospital",
+    icu_total: "COVID-19 patients in ICU",
+    ventilator_total: "COVID-19 patients in ICU on a ventilator",
+    region: "Public health unit",
+    province: "All of Ontario",
+    region_note: "Ontario does not publish cases by vaccination status for each public health unit.",
+    region_cases_caption: "Cases reported by the public health unit",
+    active_cases: "Active cases",
+    resolved_cases: "Resolved cases",
+    deaths: "Deaths",
+};
+
+static FR: Messages = Messages {
+    language_name: "Français",
+    weekdays: [
+        "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+    ],
+    months: [
+        "janvier",
+        "février",
+        "mars",
+        "avril",
+        "mai",
+        "juin",
+        "juillet",
+        "août",
+        "septembre",
+        "octobre",
+        "novembre",
+        "décembre",
+    ],
+    not_available: "s.o.",
+    source_code: "code source",
+    discontinued: "Gouvernement de l'Ontario : Veuillez noter que les données sur les cas et les hospitalisations selon le statut vaccinal ne seront plus publiées à compter du 23 juin 2022.",
+    updated: "Mise à jour",
+    previous: "Précédent",
+    next: "Suivant",
+    home: "accueil",
+    report_for: "Rapport du",
+    report_heading: "Comparaison par habitant de la COVID-19 selon le statut vaccinal",
+    detailed_link: "Cliquez ici pour le rapport détaillé",
+    rate_per_100k: "Taux pour 100 000",
+    dose0: "0 dose",
+    dose_lt2: "< 2 doses",
+    dose2: "2 doses",
+    tested_positive: "Testés positifs",
+    hosp_nonicu: "Hospitalisés hors des soins intensifs",
+    hosp_icu: "Hospitalisés aux soins intensifs",
+    detail_heading: "Rapport détaillé du",
+    back_to_compare: "Retour à la comparaison",
+    cases_heading: "Cas de COVID-19 selon le statut vaccinal",
+    hosps_heading: "Hospitalisations liées à la COVID-19 selon le statut vaccinal",
+    hosps_note: "En raison de déclarations incomplètes les fins de semaine et les jours fériés, le statut vaccinal des patients hospitalisés et aux soins intensifs n'est pas mis à jour les dimanches, les lundis et le lendemain des jours fériés.",
+    computed_heading: "Indicateurs calculés à partir des données gouvernementales ci-dessus",
+    computed_note: "L'échelle pour 100 000 est utilisée lorsque l'échelle en pour cent, plus courante, donnerait de très petits nombres décimaux. Par exemple, 1 sur 100 000 équivaut à 0,001 %, et 1 000 sur 100 000 équivaut à 1 % de la population.",
+    cases_rows: [
+        dl("Non vaccinés", "Nombre de personnes ayant obtenu un résultat positif à la COVID-19 à cette date. Une personne est considérée comme non vaccinée si elle n'a reçu aucune dose, ou si sa première dose remonte à moins de quatorze jours."),
+        dl("Partiellement vaccinés", "Nombre de personnes ayant obtenu un résultat positif à la COVID-19 à cette date. Une personne est considérée comme partiellement vaccinée si elle a reçu une dose il y a au moins quatorze jours, ou deux doses dont la deuxième remonte à moins de quatorze jours."),
+        dl("Pas entièrement vaccinés (moins de 2 doses)", "Nombre de personnes pas entièrement vaccinées ayant obtenu un résultat positif à la COVID-19 à cette date. Une personne est considérée comme entièrement vaccinée si elle a reçu deux doses et que la deuxième remonte à au moins quatorze jours. Nouvelle donnée depuis le 11 mars 2022."),
+        dl("Entièrement vaccinés", "Nombre de personnes ayant obtenu un résultat positif à la COVID-19 à cette date. Une personne est considérée comme entièrement vaccinée si elle a reçu deux doses et que la deuxième remonte à au moins quatorze jours."),
+        dl("Dose de rappel (3 doses)", "Nombre de personnes ayant reçu une dose de rappel et obtenu un résultat positif à la COVID-19 à cette date. Une personne est considérée comme ayant reçu une dose de rappel si elle a reçu trois doses et que la troisième remonte à au moins quatorze jours. Nouvelle donnée depuis le 11 mars 2022."),
+        dl("Statut vaccinal inconnu", "Nombre de personnes ayant obtenu un résultat positif à la COVID-19 à cette date, mais dont le statut vaccinal est inconnu."),
+        dl("Taux chez les non vaccinés pour 100 000", "Taux de cas de COVID-19 pour 100 000 personnes non vaccinées (calculé en divisant le nombre de cas pour un statut vaccinal par le nombre total de personnes ayant ce statut, puis en multipliant par 100 000)."),
+        dl("Taux chez les partiellement vaccinés pour 100 000", "Taux de cas de COVID-19 pour 100 000 personnes partiellement vaccinées (calculé en divisant le nombre de cas pour un statut vaccinal par le nombre total de personnes ayant ce statut, puis en multipliant par 100 000)."),
+        dl("Taux chez les pas entièrement vaccinés pour 100 000", "Taux de cas de COVID-19 pour 100 000 personnes pas entièrement vaccinées (calculé en divisant le nombre de cas pour un statut vaccinal par le nombre total de personnes ayant ce statut, puis en multipliant par 100 000). Nouveau depuis le 11 mars 2022."),
+        dl("Taux chez les entièrement vaccinés pour 100 000", "Taux de cas de COVID-19 pour 100 000 personnes entièrement vaccinées (calculé en divisant le nombre de cas pour un statut vaccinal par le nombre total de personnes ayant ce statut, puis en multipliant par 100 000)."),
+        dl("Taux chez les personnes ayant reçu une dose de rappel pour 100 000", "Taux de cas de COVID-19 pour 100 000 personnes ayant reçu une dose de rappel (calculé en divisant le nombre de cas pour un statut vaccinal par le nombre total de personnes ayant ce statut, puis en multipliant par 100 000). Nouveau depuis le 11 mars 2022."),
+        dl("Taux chez les non vaccinés pour 100 000 (moyenne mobile sur 7 jours)", "Taux moyen de cas de COVID-19 pour 100 000 au cours des 7 jours précédents pour ce statut vaccinal."),
+        dl("Taux chez les partiellement vaccinés pour 100 000 (moyenne mobile sur 7 jours)", DITTO),
+        dl("Taux chez les pas entièrement vaccinés pour 100 000 (moyenne mobile sur 7 jours)", DITTO),
+        dl("Taux chez les entièrement vaccinés pour 100 000 (moyenne mobile sur 7 jours)", DITTO),
+        dl("Taux chez les personnes ayant reçu une dose de rappel pour 100 000 (moyenne mobile sur 7 jours)", DITTO),
+    ],
+    hosps_rows: [
+        dl("Non vaccinés hospitalisés hors des soins intensifs", "Nombre de personnes admises à l'hôpital sans nécessiter de séjour aux soins intensifs. Afin de connaître le statut vaccinal des patients hospitalisés, un nouveau processus de collecte de données a été mis en place, ce qui peut entraîner des écarts avec d'autres chiffres d'hospitalisation recueillis selon un processus différent."),
+        dl("Partiellement vaccinés hospitalisés hors des soins intensifs", DITTO),
+        dl("Entièrement vaccinés hospitalisés hors des soins intensifs", DITTO),
+        dl("Non vaccinés aux soins intensifs", "Nombre de personnes hospitalisées aux soins intensifs en raison de la COVID-19. Les données sur les patients aux soins intensifs proviennent de deux sources ayant des heures d'extraction et des cycles de publication différents. La source existante (Système d'information sur les soins aux malades en phase critique, SISMPC) n'indique pas le statut vaccinal."),
+        dl("Partiellement vaccinés aux soins intensifs", DITTO),
+        dl("Entièrement vaccinés aux soins intensifs", DITTO),
+    ],
+    computed_rows: [
+        dl("Nombre de personnes non vaccinées", "Calculé en multipliant le nombre de cas pour ce statut vaccinal par 100 000, puis en divisant par le taux et en arrondissant."),
+        dl("Nombre de personnes partiellement vaccinées", DITTO),
+        dl("Nombre de personnes pas entièrement vaccinées (moins de 2 doses)", DITTO),
+        dl("Nombre de personnes entièrement vaccinées", DITTO),
+        dl("Nombre de personnes ayant reçu une dose de rappel", DITTO),
+        dl("Taux d'hospitalisation hors soins intensifs des non vaccinés pour 100 000", "Calculé en multipliant le nombre d'hospitalisations hors soins intensifs pour ce statut vaccinal par 100 000, puis en divisant par la population ayant ce statut."),
+        dl("Taux d'hospitalisation hors soins intensifs des partiellement vaccinés pour 100 000", DITTO),
+        dl("Taux d'hospitalisation hors soins intensifs des pas entièrement vaccinés pour 100 000", DITTO),
+        dl("Taux d'hospitalisation hors soins intensifs des entièrement vaccinés pour 100 000", DITTO),
+        dl("Taux d'hospitalisation aux soins intensifs des non vaccinés pour 100 000", "Calculé en multipliant le nombre d'hospitalisations aux soins intensifs pour ce statut vaccinal par 100 000, puis en divisant par la population ayant ce statut."),
+        dl("Taux d'hospitalisation aux soins intensifs des partiellement vaccinés pour 100 000", DITTO),
+        dl("Taux d'hospitalisation aux soins intensifs des pas entièrement vaccinés pour 100 000", DITTO),
+        dl("Taux d'hospitalisation aux soins intensifs des entièrement vaccinés pour 100 000", DITTO),
+    ],
+    standardised_heading: "Taux bruts et normalisés selon l'âge pour 100 000",
+    standardised_rows: [
+        dl("Taux de cas des non vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux de cas des non vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux de cas des pas entièrement vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux de cas des pas entièrement vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux de cas des entièrement vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux de cas des entièrement vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux d'hospitalisation hors soins intensifs des non vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux d'hospitalisation hors soins intensifs des non vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux d'hospitalisation hors soins intensifs des pas entièrement vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux d'hospitalisation hors soins intensifs des pas entièrement vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux d'hospitalisation hors soins intensifs des entièrement vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux d'hospitalisation hors soins intensifs des entièrement vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux d'hospitalisation aux soins intensifs des non vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux d'hospitalisation aux soins intensifs des non vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux d'hospitalisation aux soins intensifs des pas entièrement vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux d'hospitalisation aux soins intensifs des pas entièrement vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+        dl("Taux d'hospitalisation aux soins intensifs des entièrement vaccinés pour 100 000 (brut)", CRUDE_FR),
+        dl("Taux d'hospitalisation aux soins intensifs des entièrement vaccinés pour 100 000 (normalisé selon l'âge)", STANDARDISED_FR),
+    ],
+    chart_cases_title: "Cas de COVID-19 selon le statut vaccinal pour 100 000 personnes",
+    chart_nonicu_title: "Hospitalisations liées à la COVID-19 (hors soins intensifs) selon le statut vaccinal pour 100 000 personnes",
+    chart_icu_title: "Hospitalisations aux soins intensifs liées à la COVID-19 selon le statut vaccinal pour 100 000 personnes",
+    skip_to_content: "Aller au contenu",
+    day_navigation: "Navigation entre les jours",
+    compare_caption: "Taux pour 100 000 personnes selon le nombre de doses de vaccin",
+    pick_day: "Choisir un jour",
+    go: "Afficher",
+    measure: "Mesure",
+    value: "Valeur",
+    description: "Description",
+    date: "Date",
+    chart_table: "Afficher les données du graphique sous forme de tableau",
+    weekdays_short: ["lun", "mar", "mer", "jeu", "ven", "sam", "dim"],
+    calendar: "Calendrier",
+    calendar_heading: "Jours avec des données",
+    calendar_note: "Les jours avec un lien ont un rapport. La raison pour laquelle les jours entre eux n'en ont pas est indiquée sous le calendrier.",
+    gaps_heading: "Jours sans rapport",
+    gap_missing_upstream: "non publiés",
+    gap_failed_validation: "n'ont pas passé la validation",
+    gap_unmatched_join: "les hospitalisations et les cas n'ont pas été publiés tous les deux",
+    jump_to_date: "Aller à la date",
+    keyboard_help: "Raccourcis clavier : flèche gauche pour le jour précédent, flèche droite pour le jour suivant.",
+    compare_heading: "Comparaison de deux jours",
+    compare_link: "Comparer avec un autre jour",
+    compare_first: "Premier jour",
+    compare_second: "Deuxième jour",
+    change: "Variation",
+    percent_change: "Variation en %",
+    not_found_heading: "Aucun rapport pour ce jour",
+    not_found_note: "Il n'y a pas de rapport pour le jour demandé.",
+    bad_request_heading: "Requête invalide",
+    bad_request_note: "L'adresse ne contient pas un jour valide.",
+    unavailable_heading: "Données temporairement indisponibles",
+    unavailable_note: "Les rapports n'ont pas pu être chargés. Veuillez réessayer dans quelques minutes.",
+    nearest_day: "Le jour le plus proche avec un rapport est le",
+    coverage_caption: "Couverture vaccinale et occupation des hôpitaux en Ontario",
+    at_least_one_dose: "Personnes de 5 ans et plus ayant reçu au moins une dose",
+    fully_vaccinated: "Personnes de 5 ans et plus entièrement vaccinées",
+    third_dose: "Personnes de 5 ans et plus ayant reçu une troisième dose",
+    hospitalized_total: "Patients atteints de la COVID-19 hospitalisés",
+    icu_total: "Patients atteints de la COVID-19 aux soins intensifs",
+    ventilator_total: "Patients atteints de la COVID-19 aux soins intensifs sous respirateur",
+    region: "Bureau de santé publique",
+    province: "Tout l'Ontario",
+    region_note: "L'Ontario ne publie pas les cas selon le statut vaccinal pour chaque bureau de santé publique.",
+    region_cases_caption: "Cas déclarés par le bureau de santé publique",
+    active_cases: "Cas actifs",
+    resolved_cases: "Cas résolus",
+    deaths: "Décès",
+};
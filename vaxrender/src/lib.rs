@@ -0,0 +1,357 @@
+//presentation of the reports as pure functions of Index and DayReport
+//shared by the cloudflare worker and the static site generator
+mod i18n;
+mod pages;
+
+pub use askama::Error as RenderError;
+pub use i18n::Lang;
+pub use pages::{
+    render_calendar_str, render_chart_str, render_compare_str, render_detail_report_str,
+    render_error_page_str, render_feed_str, render_region_str, render_report_str, ErrorPage,
+    Headline, Host, RenderResult,
+};
+
+pub static CSS: &str = r#"
+:root {
+  --fg: #1a1a1a;
+  --bg: #ffffff;
+  --muted: #4a4a4a;
+  --border: #1a1a1a;
+  --link: #0645ad;
+  --focus: #b35900;
+  --dose0: #c62828;
+  --dose2: #2e7d32;
+  --dose-lt2: #6a1b9a;
+  color-scheme: light dark;
+}
+@media (prefers-color-scheme: dark) {
+  :root {
+    --fg: #e8e8e8;
+    --bg: #121212;
+    --muted: #bdbdbd;
+    --border: #8a8a8a;
+    --link: #8ab4f8;
+    --focus: #ffb74d;
+    --dose0: #ff8a80;
+    --dose2: #81c784;
+    --dose-lt2: #ce93d8;
+  }
+}
+*, *::before, *::after {
+  box-sizing: border-box;
+}
+body {
+  color: var(--fg);
+  background-color: var(--bg);
+  font-family: system-ui, -apple-system, "Segoe UI", Roboto, sans-serif;
+  line-height: 1.5;
+  margin: 0 auto;
+  padding: 0 1rem;
+  max-width: 60rem;
+}
+h1 {
+  font-size: 1.4rem;
+}
+h2 {
+  font-size: 1.15rem;
+}
+a {
+  color: var(--link);
+}
+a:focus-visible, button:focus-visible, input:focus-visible, summary:focus-visible {
+  outline: 3px solid var(--focus);
+  outline-offset: 2px;
+}
+.skip {
+  position: absolute;
+  left: -999px;
+}
+.skip:focus {
+  position: static;
+}
+#lang {
+  display: flex;
+  justify-content: flex-end;
+  padding-top: 0.5rem;
+}
+.table-wrap {
+  overflow-x: auto;
+}
+table {
+  border-collapse: collapse;
+  width: 100%;
+}
+caption {
+  text-align: left;
+  font-weight: bold;
+  padding-bottom: 0.5rem;
+}
+table, th, td {
+  border: 1px solid var(--border);
+}
+td, th {
+  text-align: center;
+  padding: 0.75rem;
+  vertical-align: middle;
+}
+th.label, th[scope="row"] {
+  text-align: left;
+}
+td.num {
+  text-align: right;
+  white-space: nowrap;
+}
+table.compare td {
+  font-weight: bold;
+}
+.note {
+  color: var(--muted);
+  font-size: 0.9rem;
+}
+.slidecontainer {
+  display: flex;
+  flex-wrap: wrap;
+  align-items: center;
+  gap: 0.5rem 1rem;
+  margin: 1.5rem 0;
+  max-width: 30rem;
+}
+#dayRange {
+  flex: 1 1 12rem;
+}
+.regions ul {
+  columns: 16rem;
+  margin: 1.5rem 0;
+}
+button {
+  font: inherit;
+  padding: 0.25rem 1rem;
+}
+#nav_buttons {
+  display: flex;
+  align-items: center;
+  justify-content: space-between;
+  max-width: 30rem;
+  margin: 1rem 0;
+}
+#nav_buttons a {
+  padding: 0.5rem 0;
+}
+.chart-container {
+  position: relative;
+  height: 70vh;
+  width: 100%;
+}
+details {
+  margin: 1rem 0;
+}
+footer {
+  margin-top: 4rem;
+  text-align: center;
+  color: var(--muted);
+  font-size: 0.85rem;
+}
+#main {
+  margin-top: 1rem;
+}
+.calendar {
+  display: grid;
+  grid-template-columns: repeat(auto-fill, minmax(16rem, 1fr));
+  gap: 1.5rem;
+}
+table.month td, table.month th {
+  padding: 0.25rem;
+}
+table.month td.has-data {
+  font-weight: bold;
+}
+table.month td.no-data {
+  color: var(--muted);
+}
+table.month td.gap {
+  text-decoration: line-through;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use ontariopublic::{
+        AgeRates, AgeReport, DayReport, DosesByAge, GapCause, Index, Occupancy, Phu, PhuCases,
+        Province, RegionReport, ALL_ELIGIBLE, ONTARIO, ONTARIO_2021,
+    };
+    use rust_decimal::Decimal;
+
+    fn report() -> DayReport {
+        let mut report = DayReport::default();
+        report.cases.date = NaiveDate::from_ymd(2022, 1, 2);
+        report.cases.covid19_cases_full_vac = 12345;
+        report
+    }
+
+    #[test]
+    fn report_in_both_languages() {
+        let idx = Index::from(&["20220101", "20220102", "20220103"]);
+        let en = render_report_str(&idx, &report(), Lang::En, Host::Worker).unwrap();
+        assert!(en.contains("Sunday, 2 January, 2022"));
+        assert!(en.contains(r#"href="/d/20220101/" rel="prev""#));
+        assert!(en.contains(r#"href="/d/20220103/" rel="next""#));
+        let fr = render_report_str(&idx, &report(), Lang::Fr, Host::Worker).unwrap();
+        assert!(fr.contains("dimanche 2 janvier 2022"));
+        assert!(fr.contains(r#"href="/fr/d/20220101/""#));
+    }
+
+    #[test]
+    fn province_pages_under_prefix() {
+        let qc = Province::by_code("qc").unwrap();
+        let idx = Index::from(&["20220101", "20220102"]).with_province(qc);
+        let en = render_report_str(&idx, &report(), Lang::En, Host::Worker).unwrap();
+        assert!(en.contains("in Quebec, Canada."));
+        assert!(en.contains(r#"href="/p/qc/d/20220101/" rel="prev""#));
+        assert!(!en.contains(r#"action="/r/""#));
+        let fr = render_report_str(&idx, &report(), Lang::Fr, Host::Worker).unwrap();
+        assert!(fr.contains("au Québec, Canada."));
+        assert!(fr.contains(r#"href="/fr/p/qc/d/20220101/""#));
+        let on = render_report_str(
+            &Index::from(&["20220102"]),
+            &report(),
+            Lang::En,
+            Host::Worker,
+        )
+        .unwrap();
+        assert!(on.contains("in Ontario, Canada."));
+    }
+
+    #[test]
+    fn report_shows_coverage_when_present() {
+        let idx = Index::from(&["20220102"]);
+        let en = render_report_str(&idx, &report(), Lang::En, Host::Worker).unwrap();
+        assert!(!en.contains("Vaccine coverage"));
+        let mut report = report();
+        let date = report.cases.date;
+        report.doses.push(DosesByAge {
+            date,
+            agegroup: ALL_ELIGIBLE.into(),
+            at_least_one_dose: 900,
+            fully_vaccinated: 800,
+            third_dose: None,
+            population: 1000,
+        });
+        report.occupancy = Some(Occupancy {
+            date,
+            hospitalized: Some(2419),
+            icu: Some(338),
+            icu_ventilator: None,
+        });
+        let en = render_report_str(&idx, &report, Lang::En, Host::Worker).unwrap();
+        assert!(en.contains("Vaccine coverage"));
+        assert!(en.contains("<td class=\"num\">90%</td>"));
+        assert!(en.contains("<td class=\"num\">2,419</td>"));
+        let fr = render_report_str(&idx, &report, Lang::Fr, Host::Worker).unwrap();
+        assert!(fr.contains("80\u{a0}%"));
+    }
+
+    #[test]
+    fn region_page_links_within_region() {
+        let phu = Phu::by_slug("toronto").unwrap();
+        let idx = Index::from(&["20220101", "20220102"]);
+        let date = NaiveDate::from_ymd(2022, 1, 2);
+        let mut report = RegionReport::new(phu.id, date);
+        report.cases = Some(PhuCases {
+            date,
+            phu: phu.id,
+            active: 41234,
+            resolved: 0,
+            deaths: 12,
+        });
+        let en = render_region_str(phu, &idx, &report, Lang::En, Host::Worker).unwrap();
+        assert!(en.contains("<h2>Toronto Public Health</h2>"));
+        assert!(en.contains("41,234"));
+        assert!(en.contains(r#"href="/r/toronto/d/20220101/" rel="prev""#));
+        assert!(en.contains(r#"<option value="toronto" selected>"#));
+        let fr = render_region_str(phu, &idx, &report, Lang::Fr, Host::Worker).unwrap();
+        assert!(fr.contains("Santé publique Toronto"));
+        assert!(fr.contains(r#"href="/fr/r/toronto/d/20220101/""#));
+        assert!(fr.contains(r#"action="/fr/r/""#));
+        let ottawa = Phu::by_slug("ottawa").unwrap();
+        let en = render_region_str(phu, &idx, &report, Lang::En, Host::Static(&[phu])).unwrap();
+        assert!(!en.contains("<form"));
+        assert!(en.contains(r#"<a href="/r/toronto/" aria-current="page">"#));
+        assert!(!en.contains(&format!("/r/{}/", ottawa.slug)));
+    }
+
+    #[test]
+    fn compare_shows_standardised_rates_with_ages() {
+        let idx = Index::from(&["20220102"]);
+        let en = render_compare_str(&idx, &report(), &report(), Lang::En, Host::Worker).unwrap();
+        assert!(!en.contains("age-standardised"));
+        let mut second = report();
+        let rates = |agegroup: &str, unvac: i64| AgeRates {
+            date: second.cases.date,
+            agegroup: agegroup.into(),
+            unvac: Some(Decimal::from(unvac)),
+            notfull: Some(Decimal::from(unvac)),
+            full: Some(Decimal::from(10)),
+        };
+        let ages = AgeReport {
+            cases: ONTARIO_2021
+                .iter()
+                .map(|(g, _)| rates(g, if *g == "5-11" { 200 } else { 0 }))
+                .collect(),
+            ..Default::default()
+        };
+        second.ages = Some(ages);
+        let en = render_compare_str(&idx, &report(), &second, Lang::En, Host::Worker).unwrap();
+        assert!(en.contains("Crude and age-standardised rates per 100,000"));
+        //5-11 is 1106389 of the 13622160 people of the reference
+        assert!(en.contains("<td class=\"num\">16.24</td>"));
+        let fr = render_compare_str(&idx, &report(), &second, Lang::Fr, Host::Worker).unwrap();
+        assert!(fr.contains("normalisé selon l&#x27;âge"));
+    }
+
+    #[test]
+    fn error_page_suggests_nearest() {
+        let page = render_error_page_str(
+            ErrorPage::NotFound,
+            Some("20220103"),
+            ONTARIO,
+            "/d/x/",
+            Lang::En,
+        )
+        .unwrap();
+        assert!(page.contains(r#"<a href="/d/20220103/">"#));
+        let page = render_error_page_str(ErrorPage::Unavailable, None, ONTARIO, "/d/x/", Lang::En)
+            .unwrap();
+        assert!(!page.contains("/d/20220103/"));
+    }
+
+    #[test]
+    fn calendar_lists_gaps_with_causes() {
+        let idx = Index::from(&["20220101", "20220104", "20220105"])
+            .with_skipped([(NaiveDate::from_ymd(2022, 1, 3), GapCause::UnmatchedJoin)]);
+        let en = render_calendar_str(&idx, Lang::En).unwrap();
+        assert!(en.contains(r#"<td class="no-data gap" title="not published">2</td>"#));
+        assert!(en.contains("<li>Sunday, 2 January, 2022: not published</li>"));
+        assert!(en.contains(
+            "<li>Monday, 3 January, 2022: hospitalizations and cases were not both published</li>"
+        ));
+        //days after the last report are not gaps
+        assert!(en.contains(r#"<td class="no-data">6</td>"#));
+        let full = render_calendar_str(&Index::from(&["20220101", "20220102"]), Lang::Fr).unwrap();
+        assert!(!full.contains("Jours sans rapport"));
+    }
+
+    #[test]
+    fn numbers_group_like_counts() {
+        let d = Decimal::new(123456, 2);
+        assert_eq!(Lang::En.format_decimal(d), "1,234.56");
+        //french groups with a narrow space and separates decimals with a comma
+        let fr_thousand = Lang::Fr.format_int(1234);
+        assert_eq!(Lang::Fr.format_decimal(d), format!("{},56", fr_thousand));
+        assert_eq!(Lang::En.format_decimal(Decimal::new(-5, 1)), "-0.5");
+        assert_eq!(Lang::En.format_float(2.5, 2), "2.50");
+        assert_eq!(Lang::En.format_float(1234.0, 0), "1,234");
+        assert_eq!(Lang::Fr.format_float(0.0523, 4), "0,0523");
+    }
+}
@@ -0,0 +1,988 @@
+use crate::i18n::{DetailLabel, Lang, Messages};
+use askama::Template;
+use chrono::{Datelike, NaiveDate};
+use ontariopublic::{
+    day_key, key_date, AgeReport, ChartSeries, DayReport, DosesByAge, GapCause, Index, Occupancy,
+    Outcome, Phu, Province, RegionReport, Series, PHUS, SERIES,
+};
+use rust_decimal::{prelude::ToPrimitive, Decimal, RoundingStrategy};
+use serde::Serialize;
+
+//data every page passes to layout.html
+struct Layout {
+    lang: Lang,
+    t: &'static Messages,
+    //where links to other pages start, the language and the province prefix
+    base: String,
+    //headings end with the province the page is about
+    in_province: String,
+    province_name: &'static str,
+    //the regions and the discontinuation notice are ontario's
+    ontario: bool,
+    //same page in the other language
+    alt_href: String,
+    alt_name: &'static str,
+    updated: Option<String>,
+    //the day pickers and the region selector need the worker to answer them
+    forms: bool,
+}
+
+//what serves the pages, a static host can't answer the forms so its pages link instead
+#[derive(Clone, Copy)]
+pub enum Host<'a> {
+    Worker,
+    //the public health units the static site has pages for
+    Static(&'a [&'static Phu]),
+}
+
+impl Layout {
+    fn new(lang: Lang, province: &Province, path: &str, updated: Option<String>) -> Self {
+        let other = lang.other();
+        Layout {
+            lang,
+            t: lang.messages(),
+            base: format!("{}{}", lang.prefix(), province.prefix()),
+            in_province: lang.in_province(province),
+            province_name: lang.province_name(province),
+            ontario: province.is_ontario(),
+            alt_href: format!("{}{}{}", other.prefix(), province.prefix(), path),
+            alt_name: other.messages().language_name,
+            updated,
+            forms: true,
+        }
+    }
+
+    fn on(mut self, host: Host) -> Self {
+        self.forms = matches!(host, Host::Worker);
+        self
+    }
+}
+
+#[derive(Template)]
+#[template(path = "report.html")]
+struct ReportTemplate {
+    page: Layout,
+    date: String,
+    cur_key: String,
+    inf_rate_unvax: String,
+    inf_rate_lt_2vax: String,
+    inf_rate_2vax: String,
+    icu_rate_unvax: String,
+    icu_rate_lt_2vax: String,
+    icu_rate_2vax: String,
+    hosp_rate_unvax: String,
+    hosp_rate_2vax: String,
+    hosp_rate_lt_2vax: String,
+    max_idx: usize,
+    idx: usize,
+    //bounds and value of the date picker as %Y-%m-%d
+    first_date: String,
+    last_date: String,
+    cur_date: String,
+    last_key: String,
+    //empty when import had neither the coverage nor the occupancy for the day
+    coverage: Vec<ValueRow>,
+    regions: Vec<RegionOption>,
+    prev: Option<String>,
+    next: Option<String>,
+    nav_prefix: &'static str,
+}
+
+struct ValueRow {
+    label: &'static str,
+    value: String,
+}
+
+//an entry of the public health unit selector
+struct RegionOption {
+    slug: &'static str,
+    name: &'static str,
+    selected: bool,
+}
+
+#[derive(Template)]
+#[template(path = "region.html")]
+struct RegionTemplate {
+    page: Layout,
+    name: &'static str,
+    date: String,
+    cases: Vec<ValueRow>,
+    coverage: Vec<ValueRow>,
+    regions: Vec<RegionOption>,
+    prev: Option<String>,
+    next: Option<String>,
+    //the day links of a region are under /r/<slug>/d/
+    nav_prefix: String,
+}
+
+struct DetailRow {
+    label: &'static str,
+    value: String,
+    desc: &'static str,
+}
+
+struct DetailSection {
+    heading: &'static str,
+    note: Option<&'static str>,
+    rows: Vec<DetailRow>,
+}
+
+#[derive(Template)]
+#[template(path = "detail.html")]
+struct DetailTemplate {
+    page: Layout,
+    date: String,
+    cur_key: String,
+    last_key: String,
+    sections: Vec<DetailSection>,
+    prev: Option<String>,
+    next: Option<String>,
+    nav_prefix: &'static str,
+}
+
+#[derive(Template)]
+#[template(path = "chart.html")]
+struct ChartTemplate<'a> {
+    page: Layout,
+    title: String,
+    //same data as the chart for readers that cannot see it
+    rows: Vec<ChartRow<'a>>,
+    labels: &'a [String],
+    lines: Vec<ChartLine<'a>>,
+}
+
+struct ChartRow<'a> {
+    label: &'a str,
+    values: Vec<String>,
+}
+
+//a dataset of chart.js, one per series of the outcome
+#[derive(Serialize)]
+struct ChartLine<'a> {
+    label: &'static str,
+    colour: &'static str,
+    dash: &'static [u32],
+    data: &'a [Option<f64>],
+}
+
+struct CompareRow {
+    label: &'static str,
+    first: String,
+    second: String,
+    change: String,
+    percent: String,
+}
+
+struct CompareSection {
+    heading: &'static str,
+    rows: Vec<CompareRow>,
+}
+
+#[derive(Template)]
+#[template(path = "compare.html")]
+struct CompareTemplate {
+    page: Layout,
+    first_key: String,
+    second_key: String,
+    first_date: String,
+    second_date: String,
+    first_picker: String,
+    second_picker: String,
+    min_date: String,
+    max_date: String,
+    sections: Vec<CompareSection>,
+}
+
+struct CalendarDay {
+    day: u32,
+    //set when there is a report for the day
+    key: Option<String>,
+    //why a day between the first and the last report has none
+    gap: Option<&'static str>,
+}
+
+//a run of days without a report listed below the calendar
+struct GapRow {
+    days: String,
+    cause: &'static str,
+}
+
+struct CalendarMonth {
+    name: String,
+    //weeks start on monday, None pads the days outside of the month
+    weeks: Vec<Vec<Option<CalendarDay>>>,
+}
+
+#[derive(Template)]
+#[template(path = "calendar.html")]
+struct CalendarTemplate {
+    page: Layout,
+    months: Vec<CalendarMonth>,
+    gaps: Vec<GapRow>,
+}
+
+//headline rates of one day as stored in the chart series
+pub struct Headline {
+    pub key: String,
+    pub date: NaiveDate,
+    //one per series of SERIES in its order
+    pub values: Vec<Option<f64>>,
+}
+
+impl Headline {
+    //labels are the chart labels, series the values of SERIES in its order
+    pub fn from_series(labels: &[String], series: &[Vec<Option<f64>>]) -> Vec<Headline> {
+        labels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, label)| {
+                let date = NaiveDate::parse_from_str(label, "%Y-%m-%d").ok()?;
+                Some(Headline {
+                    key: date.format("%Y%m%d").to_string(),
+                    date,
+                    values: series.iter().map(|v| v.get(i).copied().flatten()).collect(),
+                })
+            })
+            .collect()
+    }
+}
+
+struct FeedEntry {
+    key: String,
+    date: String,
+    summary: String,
+}
+
+#[derive(Template)]
+#[template(path = "feed.xml")]
+struct FeedTemplate<'a> {
+    lang: Lang,
+    t: &'static Messages,
+    //scheme and host the feed was requested from, atom wants absolute links
+    base: &'a str,
+    //the language and the province prefix of the linked pages
+    prefix: String,
+    province_prefix: String,
+    in_province: String,
+    updated: String,
+    entries: Vec<FeedEntry>,
+}
+
+//the kinds of error pages, the worker decides which one a failure gets
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorPage {
+    NotFound,
+    BadRequest,
+    Unavailable,
+}
+
+#[derive(Template)]
+#[template(path = "error.html")]
+struct ErrorTemplate {
+    page: Layout,
+    heading: &'static str,
+    note: &'static str,
+    //key and formatted date of the closest day with a report
+    nearest: Option<(String, String)>,
+}
+
+fn chart_title(t: &'static Messages, outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Cases => t.chart_cases_title,
+        Outcome::NonIcu => t.chart_nonicu_title,
+        Outcome::Icu => t.chart_icu_title,
+    }
+}
+
+//what the rates of the outcome count, as the report table names them
+fn outcome_heading(t: &'static Messages, outcome: Outcome) -> &'static str {
+    match outcome {
+        Outcome::Cases => t.tested_positive,
+        Outcome::NonIcu => t.hosp_nonicu,
+        Outcome::Icu => t.hosp_icu,
+    }
+}
+
+//how a metric is shown, counts and populations are whole numbers
+#[derive(Debug, Clone, Copy)]
+enum Unit {
+    Count,
+    Rate,
+    Population,
+    Percent,
+}
+
+impl Unit {
+    fn format(&self, lang: Lang, d: Decimal) -> String {
+        match self {
+            Unit::Rate => lang.format_decimal(d),
+            Unit::Percent => lang.format_percent(lang.format_decimal(d)),
+            Unit::Count | Unit::Population => lang.format_int(
+                d.round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                    .to_i64()
+                    .unwrap_or(0),
+            ),
+        }
+    }
+}
+
+struct Metric {
+    unit: Unit,
+    value: Option<Decimal>,
+}
+
+impl Metric {
+    fn count(n: i64) -> Self {
+        Metric::opt_count(Some(n))
+    }
+
+    fn opt_count(n: Option<i64>) -> Self {
+        Metric {
+            unit: Unit::Count,
+            value: n.map(Decimal::from),
+        }
+    }
+
+    fn rate(d: Decimal) -> Self {
+        Metric::opt_rate(Some(d))
+    }
+
+    fn opt_rate(d: Option<Decimal>) -> Self {
+        Metric {
+            unit: Unit::Rate,
+            value: d,
+        }
+    }
+
+    fn population(d: Decimal) -> Self {
+        Metric::opt_population(Some(d))
+    }
+
+    fn opt_population(d: Option<Decimal>) -> Self {
+        Metric {
+            unit: Unit::Population,
+            value: d,
+        }
+    }
+
+    fn format(&self, lang: Lang) -> String {
+        opt_format(lang, self.unit, self.value)
+    }
+}
+
+fn opt_format(lang: Lang, unit: Unit, d: Option<Decimal>) -> String {
+    d.map_or_else(
+        || String::from(lang.messages().not_available),
+        |v| unit.format(lang, v),
+    )
+}
+
+fn phu_name(lang: Lang, phu: &Phu) -> &'static str {
+    match lang {
+        Lang::En => phu.name,
+        Lang::Fr => phu.name_fr,
+    }
+}
+
+//a static host only lists the units it has pages for
+fn region_options(lang: Lang, current: Option<&Phu>, host: Host) -> Vec<RegionOption> {
+    PHUS.iter()
+        .filter(|phu| match host {
+            Host::Worker => true,
+            Host::Static(phus) => phus.contains(phu),
+        })
+        .map(|phu| RegionOption {
+            slug: phu.slug,
+            name: phu_name(lang, phu),
+            selected: current == Some(phu),
+        })
+        .collect()
+}
+
+fn opt_dec(lang: Lang, d: Option<Decimal>) -> String {
+    opt_format(lang, Unit::Rate, d)
+}
+
+//coverage of everyone eligible and the hospital occupancy next to the rates
+fn coverage_rows(
+    lang: Lang,
+    doses: Option<&DosesByAge>,
+    occupancy: Option<&Occupancy>,
+) -> Vec<ValueRow> {
+    let t = lang.messages();
+    let row = |label, unit, value| ValueRow {
+        label,
+        value: opt_format(lang, unit, value),
+    };
+    let mut rows = vec![];
+    if let Some(d) = doses {
+        rows.push(row(
+            t.at_least_one_dose,
+            Unit::Percent,
+            Some(d.at_least_one_dose_pct()),
+        ));
+        rows.push(row(
+            t.fully_vaccinated,
+            Unit::Percent,
+            Some(d.fully_vaccinated_pct()),
+        ));
+        rows.push(row(t.third_dose, Unit::Percent, d.third_dose_pct()));
+    }
+    if let Some(o) = occupancy {
+        let count = |n: Option<i64>| n.map(Decimal::from);
+        rows.push(row(
+            t.hospitalized_total,
+            Unit::Count,
+            count(o.hospitalized),
+        ));
+        rows.push(row(t.icu_total, Unit::Count, count(o.icu)));
+        rows.push(row(
+            t.ventilator_total,
+            Unit::Count,
+            count(o.icu_ventilator),
+        ));
+    }
+    rows
+}
+
+//every count and rate of a report in the order of the detail catalogue sections
+fn report_metrics(report: &DayReport) -> [Vec<Metric>; 3] {
+    let cases = &report.cases;
+    let hosps = &report.hosps;
+    [
+        vec![
+            Metric::opt_count(cases.covid19_cases_unvac),
+            Metric::opt_count(cases.covid19_cases_partial_vac),
+            Metric::opt_count(cases.covid19_cases_notfull_vac),
+            Metric::count(cases.covid19_cases_full_vac),
+            Metric::opt_count(cases.covid19_cases_boost_vac),
+            Metric::opt_count(cases.covid19_cases_vac_unknown),
+            Metric::opt_rate(cases.cases_unvac_rate_per100k),
+            Metric::opt_rate(cases.cases_partial_vac_rate_per100k),
+            Metric::opt_rate(cases.cases_notfull_vac_rate_per100k),
+            Metric::rate(cases.cases_full_vac_rate_per100k),
+            Metric::opt_rate(cases.cases_boost_vac_rate_per100k),
+            Metric::opt_rate(cases.cases_unvac_rate_7ma),
+            Metric::opt_rate(cases.cases_partial_vac_rate_7ma),
+            Metric::opt_rate(cases.cases_notfull_vac_rate_7ma),
+            Metric::opt_rate(cases.cases_full_vac_rate_7ma),
+            Metric::opt_rate(cases.cases_boost_vac_rate_7ma),
+        ],
+        vec![
+            Metric::count(hosps.hospitalnonicu_unvac),
+            Metric::count(hosps.hospitalnonicu_partial_vac),
+            Metric::count(hosps.hospitalnonicu_full_vac),
+            Metric::count(hosps.icu_unvac),
+            Metric::count(hosps.icu_partial_vac),
+            Metric::count(hosps.icu_full_vac),
+        ],
+        vec![
+            Metric::opt_population(cases.calc_unvac_population()),
+            Metric::opt_population(cases.calc_partial_vac_population()),
+            Metric::opt_population(cases.calc_notfull_vac_population()),
+            Metric::population(cases.calc_full_vac_population()),
+            Metric::opt_population(cases.calc_boost_vac_population()),
+            Metric::opt_rate(report.nonicu_unvac_rate_per100k()),
+            Metric::opt_rate(report.nonicu_partial_vac_rate_per100k()),
+            Metric::opt_rate(report.nonicu_notfull_vac_rate_per100k()),
+            Metric::rate(report.nonicu_full_vac_rate_per100k()),
+            Metric::opt_rate(report.icu_unvac_rate_per100k()),
+            Metric::opt_rate(report.icu_partial_vac_rate_per100k()),
+            Metric::opt_rate(report.icu_notfull_vac_rate_per100k()),
+            Metric::rate(report.icu_full_vac_rate_per100k()),
+        ],
+    ]
+}
+
+//each crude rate followed by the age standardised one, not available without rates by age
+fn standardised_metrics(report: &DayReport) -> Vec<Metric> {
+    let cases = &report.cases;
+    let ages = report.ages.as_ref();
+    let crude = [
+        [
+            cases.cases_unvac_rate_per100k,
+            cases.cases_notfull_vac_rate_per100k,
+            Some(cases.cases_full_vac_rate_per100k),
+        ],
+        [
+            report.nonicu_unvac_rate_per100k(),
+            report.nonicu_notfull_vac_rate_per100k(),
+            Some(report.nonicu_full_vac_rate_per100k()),
+        ],
+        [
+            report.icu_unvac_rate_per100k(),
+            report.icu_notfull_vac_rate_per100k(),
+            Some(report.icu_full_vac_rate_per100k()),
+        ],
+    ];
+    let standardised = [
+        ages.and_then(AgeReport::standardised_cases),
+        ages.and_then(AgeReport::standardised_nonicu),
+        ages.and_then(AgeReport::standardised_icu),
+    ];
+    crude
+        .into_iter()
+        .zip(standardised)
+        .flat_map(|(crude, s)| {
+            let s = [s.map(|s| s.unvac), s.map(|s| s.notfull), s.map(|s| s.full)];
+            crude
+                .into_iter()
+                .zip(s)
+                .flat_map(|(c, s)| [Metric::opt_rate(c), Metric::opt_rate(s)])
+        })
+        .collect()
+}
+
+//zips the catalogue labels with the values in the same order
+fn rows(labels: &'static [DetailLabel], metrics: &[Metric], lang: Lang) -> Vec<DetailRow> {
+    labels
+        .iter()
+        .zip(metrics)
+        .map(|(l, m)| DetailRow {
+            label: l.label,
+            value: m.format(lang),
+            desc: l.desc,
+        })
+        .collect()
+}
+
+//relative change from a to b in percent, undefined when starting from zero
+fn percent_change(a: Decimal, b: Decimal) -> Option<Decimal> {
+    if a.is_zero() {
+        return None;
+    }
+    Some((b - a) * Decimal::ONE_HUNDRED / a)
+}
+
+fn compare_rows(
+    labels: &'static [DetailLabel],
+    first: &[Metric],
+    second: &[Metric],
+    lang: Lang,
+) -> Vec<CompareRow> {
+    let na = lang.messages().not_available;
+    labels
+        .iter()
+        .zip(first.iter().zip(second))
+        .map(|(l, (a, b))| {
+            let (change, percent) = match (a.value, b.value) {
+                (Some(av), Some(bv)) => (
+                    signed(b.unit.format(lang, bv - av), bv - av),
+                    percent_change(av, bv).map_or_else(
+                        || String::from(na),
+                        |p| lang.format_percent(signed(lang.format_decimal(p), p)),
+                    ),
+                ),
+                _ => (String::from(na), String::from(na)),
+            };
+            CompareRow {
+                label: l.label,
+                first: a.format(lang),
+                second: b.format(lang),
+                change,
+                percent,
+            }
+        })
+        .collect()
+}
+
+//positive changes get an explicit plus sign
+fn signed(formatted: String, d: Decimal) -> String {
+    if d.is_sign_positive() && !d.is_zero() {
+        format!("+{}", formatted)
+    } else {
+        formatted
+    }
+}
+
+fn picker_date(day: Option<NaiveDate>) -> String {
+    day.map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+//rendering can still fail at runtime (a failing filter or Display impl), the caller decides the status
+pub type RenderResult = Result<String, askama::Error>;
+
+pub fn render_report_str(
+    index: &Index,
+    report: &DayReport,
+    lang: Lang,
+    host: Host,
+) -> RenderResult {
+    let cur_key = report.key();
+    ReportTemplate {
+        page: Layout::new(
+            lang,
+            index.province(),
+            &format!("/d/{}/", cur_key),
+            Some(lang.format_datetime(&index.updated)),
+        )
+        .on(host),
+        date: lang.format_date(report.cases.date),
+        inf_rate_unvax: opt_dec(lang, report.cases.cases_unvac_rate_per100k),
+        inf_rate_lt_2vax: opt_dec(lang, report.cases.cases_notfull_vac_rate_per100k),
+        inf_rate_2vax: lang.format_decimal(report.cases.cases_full_vac_rate_per100k),
+        icu_rate_unvax: opt_dec(lang, report.icu_unvac_rate_per100k()),
+        icu_rate_lt_2vax: opt_dec(lang, report.icu_notfull_vac_rate_per100k()),
+        icu_rate_2vax: lang.format_decimal(report.icu_full_vac_rate_per100k()),
+        hosp_rate_unvax: opt_dec(lang, report.nonicu_unvac_rate_per100k()),
+        hosp_rate_2vax: lang.format_decimal(report.nonicu_full_vac_rate_per100k()),
+        hosp_rate_lt_2vax: opt_dec(lang, report.nonicu_notfull_vac_rate_per100k()),
+        max_idx: index.len().saturating_sub(1),
+        idx: index
+            .idx(report.cases.date)
+            .unwrap_or_else(|| index.len().saturating_sub(1)),
+        first_date: picker_date(index.first()),
+        last_date: picker_date(index.last()),
+        cur_date: report.cases.date.format("%Y-%m-%d").to_string(),
+        last_key: index.last().map(day_key).unwrap_or_default(),
+        coverage: coverage_rows(lang, report.all_eligible_doses(), report.occupancy.as_ref()),
+        regions: region_options(lang, None, host),
+        prev: index.prev(report.cases.date).map(day_key),
+        next: index.next(report.cases.date).map(day_key),
+        cur_key,
+        nav_prefix: "d",
+    }
+    .render()
+}
+
+//a day of a public health unit, index holds the days of that unit
+pub fn render_region_str(
+    phu: &Phu,
+    index: &Index,
+    report: &RegionReport,
+    lang: Lang,
+    host: Host,
+) -> RenderResult {
+    let t = lang.messages();
+    let count = |n: Option<i64>| opt_format(lang, Unit::Count, n.map(Decimal::from));
+    let c = report.cases.as_ref();
+    let cases = vec![
+        ValueRow {
+            label: t.active_cases,
+            value: count(c.map(|c| c.active)),
+        },
+        ValueRow {
+            label: t.resolved_cases,
+            value: count(c.map(|c| c.resolved)),
+        },
+        ValueRow {
+            label: t.deaths,
+            value: count(c.map(|c| c.deaths)),
+        },
+    ];
+    RegionTemplate {
+        page: Layout::new(
+            lang,
+            index.province(),
+            &format!("/r/{}/d/{}/", phu.slug, report.key()),
+            Some(lang.format_datetime(&index.updated)),
+        )
+        .on(host),
+        name: phu_name(lang, phu),
+        date: lang.format_date(report.date),
+        cases,
+        coverage: coverage_rows(lang, report.all_eligible_doses(), None),
+        regions: region_options(lang, Some(phu), host),
+        prev: index.prev(report.date).map(day_key),
+        next: index.next(report.date).map(day_key),
+        nav_prefix: format!("r/{}/d", phu.slug),
+    }
+    .render()
+}
+
+pub fn render_detail_report_str(index: &Index, report: &DayReport, lang: Lang) -> RenderResult {
+    let t = lang.messages();
+    let [cases_values, hosps_values, computed_values] = report_metrics(report);
+    let sections = vec![
+        DetailSection {
+            heading: t.cases_heading,
+            note: None,
+            rows: rows(&t.cases_rows, &cases_values, lang),
+        },
+        DetailSection {
+            heading: t.hosps_heading,
+            note: Some(t.hosps_note),
+            rows: rows(&t.hosps_rows, &hosps_values, lang),
+        },
+        DetailSection {
+            heading: t.computed_heading,
+            note: Some(t.computed_note),
+            rows: rows(&t.computed_rows, &computed_values, lang),
+        },
+    ];
+    let cur_key = report.key();
+    DetailTemplate {
+        page: Layout::new(
+            lang,
+            index.province(),
+            &format!("/dd/{}/", cur_key),
+            Some(lang.format_datetime(&index.updated)),
+        ),
+        date: lang.format_date(report.cases.date),
+        last_key: index.last().map(day_key).unwrap_or_default(),
+        sections,
+        prev: index.prev(report.cases.date).map(day_key),
+        next: index.next(report.cases.date).map(day_key),
+        cur_key,
+        nav_prefix: "dd",
+    }
+    .render()
+}
+
+//chart holds the lines of the outcome's series in the order of SERIES
+pub fn render_chart_str(
+    outcome: Outcome,
+    province: &Province,
+    lang: Lang,
+    chart: &ChartSeries,
+) -> RenderResult {
+    let page = Layout::new(lang, province, &outcome.path(), None);
+    let t = page.t;
+    let series: Vec<&Series> = outcome.series().collect();
+    let value = |n: usize, line: &[Option<f64>], i: usize| match line.get(i).copied().flatten() {
+        Some(v) => lang.format_float(v, series[n].transform.places(v)),
+        None => String::from(t.not_available),
+    };
+    let rows = chart
+        .labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| ChartRow {
+            label,
+            values: chart
+                .lines
+                .iter()
+                .enumerate()
+                .map(|(n, line)| value(n, line, i))
+                .collect(),
+        })
+        .collect();
+    let lines = outcome
+        .series()
+        .zip(&chart.lines)
+        .map(|(s, data)| ChartLine {
+            label: lang.series_label(s),
+            colour: s.colour,
+            dash: s.dash,
+            data,
+        })
+        .collect();
+    ChartTemplate {
+        title: format!("{} {}", chart_title(t, outcome), page.in_province),
+        rows,
+        page,
+        labels: &chart.labels,
+        lines,
+    }
+    .render()
+}
+
+fn gap_cause(lang: Lang, cause: GapCause) -> &'static str {
+    let t = lang.messages();
+    match cause {
+        GapCause::MissingUpstream => t.gap_missing_upstream,
+        GapCause::FailedValidation => t.gap_failed_validation,
+        GapCause::UnmatchedJoin => t.gap_unmatched_join,
+    }
+}
+
+//lays out every month between the first and the last day in the index
+fn calendar_months(index: &Index, lang: Lang) -> Vec<CalendarMonth> {
+    let (first, last) = match (index.first(), index.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return vec![],
+    };
+    let mut months = vec![];
+    let mut month_start = NaiveDate::from_ymd(first.year(), first.month(), 1);
+    while month_start <= last {
+        let mut weeks = vec![];
+        let mut week: Vec<Option<CalendarDay>> = (0..month_start.weekday().num_days_from_monday())
+            .map(|_| None)
+            .collect();
+        let mut day = month_start;
+        while day.month() == month_start.month() {
+            week.push(Some(CalendarDay {
+                day: day.day(),
+                key: index.contains(day).then(|| day_key(day)),
+                gap: index.cause(day).map(|c| gap_cause(lang, c)),
+            }));
+            if week.len() == 7 {
+                weeks.push(std::mem::take(&mut week));
+            }
+            day = day.succ();
+        }
+        if !week.is_empty() {
+            week.resize_with(7, || None);
+            weeks.push(week);
+        }
+        months.push(CalendarMonth {
+            name: lang.format_month(month_start.year(), month_start.month0()),
+            weeks,
+        });
+        month_start = day;
+    }
+    months
+}
+
+pub fn render_calendar_str(index: &Index, lang: Lang) -> RenderResult {
+    CalendarTemplate {
+        page: Layout::new(
+            lang,
+            index.province(),
+            "/calendar/",
+            Some(lang.format_datetime(&index.updated)),
+        ),
+        months: calendar_months(index, lang),
+        gaps: index
+            .gaps()
+            .into_iter()
+            .map(|g| GapRow {
+                days: if g.first == g.last {
+                    lang.format_date(g.first)
+                } else {
+                    format!(
+                        "{} – {}",
+                        lang.format_date(g.first),
+                        lang.format_date(g.last)
+                    )
+                },
+                cause: gap_cause(lang, g.cause),
+            })
+            .collect(),
+    }
+    .render()
+}
+
+pub fn render_compare_str(
+    index: &Index,
+    first: &DayReport,
+    second: &DayReport,
+    lang: Lang,
+    host: Host,
+) -> RenderResult {
+    let t = lang.messages();
+    let [a_cases, a_hosps, a_computed] = report_metrics(first);
+    let [b_cases, b_hosps, b_computed] = report_metrics(second);
+    let mut sections = vec![
+        CompareSection {
+            heading: t.cases_heading,
+            rows: compare_rows(&t.cases_rows, &a_cases, &b_cases, lang),
+        },
+        CompareSection {
+            heading: t.hosps_heading,
+            rows: compare_rows(&t.hosps_rows, &a_hosps, &b_hosps, lang),
+        },
+        CompareSection {
+            heading: t.computed_heading,
+            rows: compare_rows(&t.computed_rows, &a_computed, &b_computed, lang),
+        },
+    ];
+    if first.ages.is_some() || second.ages.is_some() {
+        sections.push(CompareSection {
+            heading: t.standardised_heading,
+            rows: compare_rows(
+                &t.standardised_rows,
+                &standardised_metrics(first),
+                &standardised_metrics(second),
+                lang,
+            ),
+        });
+    }
+    CompareTemplate {
+        page: Layout::new(
+            lang,
+            index.province(),
+            &format!("/cmp/{}/{}/", first.key(), second.key()),
+            Some(lang.format_datetime(&index.updated)),
+        )
+        .on(host),
+        first_key: first.key(),
+        second_key: second.key(),
+        first_date: lang.format_date(first.cases.date),
+        second_date: lang.format_date(second.cases.date),
+        first_picker: first.cases.date.format("%Y-%m-%d").to_string(),
+        second_picker: second.cases.date.format("%Y-%m-%d").to_string(),
+        min_date: picker_date(index.first()),
+        max_date: picker_date(index.last()),
+        sections,
+    }
+    .render()
+}
+
+fn headline_summary(h: &Headline, lang: Lang) -> String {
+    let t = lang.messages();
+    let outcomes: Vec<String> = Outcome::ALL
+        .into_iter()
+        .map(|outcome| {
+            let rates: Vec<String> = SERIES
+                .iter()
+                .zip(&h.values)
+                .filter(|(s, _)| s.outcome() == outcome)
+                .map(|(s, v)| {
+                    let value = v.map_or_else(
+                        || String::from(t.not_available),
+                        |f| lang.format_float(f, s.transform.places(f)),
+                    );
+                    format!("{} {}", lang.series_label(s), value)
+                })
+                .collect();
+            format!("{}: {}", outcome_heading(t, outcome), rates.join(", "))
+        })
+        .collect();
+    format!("{}. {}.", t.rate_per_100k, outcomes.join(". "))
+}
+
+//atom feed with one entry per day, newest first
+pub fn render_feed_str(
+    index: &Index,
+    headlines: &[Headline],
+    base: &str,
+    lang: Lang,
+) -> RenderResult {
+    let entries = headlines
+        .iter()
+        .rev()
+        .map(|h| FeedEntry {
+            key: h.key.clone(),
+            date: lang.format_date(h.date),
+            summary: headline_summary(h, lang),
+        })
+        .collect();
+    let province = index.province();
+    FeedTemplate {
+        lang,
+        t: lang.messages(),
+        base,
+        prefix: format!("{}{}", lang.prefix(), province.prefix()),
+        province_prefix: province.prefix(),
+        in_province: lang.in_province(province),
+        updated: index.updated.to_rfc3339(),
+        entries,
+    }
+    .render()
+}
+
+//error page for a request to path, nearest is the key of the closest day with a report
+pub fn render_error_page_str(
+    page: ErrorPage,
+    nearest: Option<&str>,
+    province: &Province,
+    path: &str,
+    lang: Lang,
+) -> RenderResult {
+    let t = lang.messages();
+    let (heading, note) = match page {
+        ErrorPage::NotFound => (t.not_found_heading, t.not_found_note),
+        ErrorPage::BadRequest => (t.bad_request_heading, t.bad_request_note),
+        ErrorPage::Unavailable => (t.unavailable_heading, t.unavailable_note),
+    };
+    let nearest = nearest.and_then(|key| Some((key.to_string(), lang.format_date(key_date(key)?))));
+    ErrorTemplate {
+        page: Layout::new(lang, province, path, None),
+        heading,
+        note,
+        nearest,
+    }
+    .render()
+}
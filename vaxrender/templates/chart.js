@@ -0,0 +1,28 @@
+window.addEventListener("load", (event) => {
+  //line colours come from the stylesheet so they follow the light or dark theme
+  const style = getComputedStyle(document.documentElement);
+  const colour = (name) => style.getPropertyValue(name).trim();
+  const data = {
+    labels: labels,
+    datasets: lines.map((line) => ({
+      label: line.label,
+      data: line.data,
+      fill: false,
+      borderColor: colour(line.colour),
+      borderDash: line.dash,
+      tension: 0.1
+    }))
+  };
+  const config = {
+    type: 'line',
+    data: data,
+    options: {
+      responsive: true,
+      maintainAspectRatio: false
+    }
+  };
+  const myChart = new Chart(
+    document.getElementById('myChart'),
+     config
+  );
+});
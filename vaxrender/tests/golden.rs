@@ -0,0 +1,151 @@
+//renders every page type from a small fixture and compares them with golden files
+//run with UPDATE_GOLDEN=1 to rewrite the golden files after an intentional change
+use ontariopublic::{day_key, ChartSeries, DayReport, Index, Outcome, ONTARIO, SERIES};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use vaxrender::{
+    render_calendar_str, render_chart_str, render_compare_str, render_detail_report_str,
+    render_error_page_str, render_feed_str, render_report_str, ErrorPage, Headline, Host, Lang,
+};
+
+#[derive(Deserialize)]
+struct Entry {
+    key: String,
+    value: String,
+}
+
+//four days around the end of january 2022 in the same format import writes them
+struct Fixture(HashMap<String, String>);
+
+impl Fixture {
+    fn load() -> Fixture {
+        let fname: PathBuf = [
+            env!("CARGO_MANIFEST_DIR"),
+            "tests",
+            "fixtures",
+            "bulk.jsonl",
+        ]
+        .iter()
+        .collect();
+        let entries = fs::read_to_string(fname)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<Entry>(l).unwrap())
+            .map(|e| (e.key, e.value))
+            .collect();
+        Fixture(entries)
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, key: &str) -> T {
+        serde_json::from_str(&self.0[key]).unwrap()
+    }
+
+    fn series(&self, key: &str) -> Vec<Option<f64>> {
+        self.get(key)
+    }
+}
+
+fn assert_golden(name: &str, actual: &str) {
+    let golden: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", name]
+        .iter()
+        .collect();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::create_dir_all(golden.parent().unwrap()).unwrap();
+        fs::write(&golden, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&golden)
+        .unwrap_or_else(|e| panic!("{} {}, run with UPDATE_GOLDEN=1", golden.display(), e));
+    for (line, (a, e)) in actual.lines().zip(expected.lines()).enumerate() {
+        assert_eq!(a, e, "{} differs at line {}", name, line + 1);
+    }
+    assert_eq!(
+        actual.lines().count(),
+        expected.lines().count(),
+        "{} has a different number of lines",
+        name
+    );
+}
+
+fn for_each_lang(page: &str, render: impl Fn(Lang) -> String) {
+    for lang in [Lang::En, Lang::Fr] {
+        assert_golden(&format!("{}/{}", lang.code(), page), &render(lang));
+    }
+}
+
+#[test]
+fn report_pages() {
+    let fixture = Fixture::load();
+    let index: Index = fixture.get("index");
+    let first: DayReport = fixture.get("20220130");
+    let middle: DayReport = fixture.get("20220131");
+    let latest: DayReport = fixture.get(&day_key(index.last().unwrap()));
+    for_each_lang("report_first.html", |lang| {
+        render_report_str(&index, &first, lang, Host::Worker).unwrap()
+    });
+    for_each_lang("report.html", |lang| {
+        render_report_str(&index, &middle, lang, Host::Worker).unwrap()
+    });
+    for_each_lang("report_latest.html", |lang| {
+        render_report_str(&index, &latest, lang, Host::Worker).unwrap()
+    });
+    for_each_lang("detail.html", |lang| {
+        render_detail_report_str(&index, &middle, lang).unwrap()
+    });
+    for_each_lang("compare.html", |lang| {
+        render_compare_str(&index, &first, &latest, lang, Host::Worker).unwrap()
+    });
+    //without the worker the forms give way to links
+    for_each_lang("report_static.html", |lang| {
+        render_report_str(&index, &middle, lang, Host::Static(&[])).unwrap()
+    });
+}
+
+#[test]
+fn chart_pages() {
+    let fixture = Fixture::load();
+    let labels: Vec<String> = fixture.get("labels");
+    for outcome in Outcome::ALL {
+        let chart = ChartSeries {
+            labels: labels.clone(),
+            lines: outcome.series().map(|s| fixture.series(s.key)).collect(),
+        };
+        for_each_lang(&format!("chart_{}.html", outcome.name()), |lang| {
+            render_chart_str(outcome, ONTARIO, lang, &chart).unwrap()
+        });
+    }
+}
+
+#[test]
+fn index_pages() {
+    let fixture = Fixture::load();
+    let index: Index = fixture.get("index");
+    let labels: Vec<String> = fixture.get("labels");
+    let series: Vec<Vec<Option<f64>>> = SERIES.iter().map(|s| fixture.series(s.key)).collect();
+    let headlines = Headline::from_series(&labels, &series);
+    for_each_lang("calendar.html", |lang| {
+        render_calendar_str(&index, lang).unwrap()
+    });
+    for_each_lang("feed.atom", |lang| {
+        render_feed_str(&index, &headlines, "https://vax.labath.ca", lang).unwrap()
+    });
+}
+
+#[test]
+fn error_pages() {
+    for (page, name) in [
+        (ErrorPage::NotFound, "not_found.html"),
+        (ErrorPage::BadRequest, "bad_request.html"),
+        (ErrorPage::Unavailable, "unavailable.html"),
+    ] {
+        let nearest = match page {
+            ErrorPage::NotFound => Some("20220131"),
+            _ => None,
+        };
+        for_each_lang(&format!("error_{}", name), |lang| {
+            render_error_page_str(page, nearest, ONTARIO, "/d/20220129/", lang).unwrap()
+        });
+    }
+}